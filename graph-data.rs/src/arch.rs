@@ -0,0 +1,126 @@
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use cincinnati::Release;
+use semver::{Identifier, Version};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+// Metadata key which holds release architecture
+static ARCH_KEY: &str = "io.openshift.upgrades.graph.release.arch";
+
+/// Architecture of release images
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arch {
+    Amd64,
+    Ppc64le,
+    S390x,
+}
+
+impl Arch {
+    /// Architecture of releases which don't specify one
+    pub const DEFAULT: Arch = Arch::Amd64;
+
+    pub const ALL: [Arch; 3] = [Arch::Amd64, Arch::Ppc64le, Arch::S390x];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Arch::Amd64 => "amd64",
+            Arch::Ppc64le => "ppc64le",
+            Arch::S390x => "s390x",
+        }
+    }
+}
+
+impl FromStr for Arch {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Fallible<Self> {
+        match s {
+            "amd64" => Ok(Arch::Amd64),
+            "ppc64le" => Ok(Arch::Ppc64le),
+            "s390x" => Ok(Arch::S390x),
+            _ => Err(anyhow::anyhow!(
+                "Unknown architecture {:?}, expected one of amd64, ppc64le, s390x",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse build metadata of a version, which names the architecture if present
+fn build_arch(version: &Version) -> Fallible<Option<Arch>> {
+    match version.build.as_slice() {
+        [] => Ok(None),
+        [Identifier::AlphaNumeric(build)] => Ok(Some(Arch::from_str(build)?)),
+        _ => Err(anyhow::anyhow!(
+            "Build metadata of {} must be a single architecture",
+            version
+        )),
+    }
+}
+
+/// Version mentioned in graph data. Versions with build metadata, i.e. `4.3.29+s390x`,
+/// only apply to releases of that architecture
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ArchVersion {
+    /// Version without build metadata
+    pub version: Version,
+    pub arch: Option<Arch>,
+}
+
+impl ArchVersion {
+    pub fn parse(version: &Version) -> Fallible<Self> {
+        let arch = build_arch(version)?;
+        let mut version = version.clone();
+        version.build.clear();
+        Ok(ArchVersion { version, arch })
+    }
+
+    /// Whether this applies to the release of given version and architecture
+    pub fn matches(&self, version: &Version, arch: Arch) -> bool {
+        *version == self.version
+            && match self.arch {
+                Some(a) => a == arch,
+                None => true,
+            }
+    }
+}
+
+impl fmt::Display for ArchVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.arch {
+            Some(arch) => write!(f, "{}+{}", self.version, arch),
+            None => write!(f, "{}", self.version),
+        }
+    }
+}
+
+/// Version without build metadata and architecture of a scraped release.
+/// Architecture is taken from build metadata, release metadata or the default
+pub fn release_arch(release: &Release) -> Fallible<(Version, Arch)> {
+    let full_version = Version::from_str(release.version())
+        .context(format!("Parsing release version {}", release.version()))?;
+    let mut version = full_version.clone();
+    version.build.clear();
+
+    let arch = match build_arch(&full_version)? {
+        Some(arch) => arch,
+        None => match release {
+            Release::Concrete(c) => match c.metadata.get(ARCH_KEY) {
+                Some(arch) => Arch::from_str(arch)
+                    .context(format!("Parsing architecture of {}", release.version()))?,
+                None => Arch::DEFAULT,
+            },
+            _ => Arch::DEFAULT,
+        },
+    };
+    Ok((version, arch))
+}
@@ -0,0 +1,259 @@
+use crate::channels;
+use crate::fmt::{self, ChannelFile};
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::graph_data_model::Channel;
+use semver::Version;
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+// Lines of context around changes in printed diffs
+static DIFF_CONTEXT: usize = 3;
+
+/// Channel file loaded for backfilling
+struct Loaded {
+    path: PathBuf,
+    channel: Channel,
+    file: ChannelFile,
+    added: Vec<Version>,
+}
+
+impl Loaded {
+    fn listed(&self) -> impl Iterator<Item = &Version> {
+        self.channel.versions.iter().chain(self.added.iter())
+    }
+
+    /// Whether the channel lists the version. Entries without build metadata apply to all architectures
+    fn covers(&self, version: &Version) -> bool {
+        self.listed()
+            .any(|v| v == version && (v.build.is_empty() || v.build == version.build))
+    }
+
+    /// Whether the channel lists the version for specific architectures only
+    fn lists_arch_specific(&self, version: &Version) -> bool {
+        self.listed().any(|v| v == version && !v.build.is_empty())
+    }
+}
+
+// Channel weight, major and minor version
+type ChannelKey = (String, u64, u64);
+
+/// Versions of `major.minor` listed in the channel
+fn minor_versions(loaded: &Loaded, major: u64, minor: u64) -> Vec<Version> {
+    loaded
+        .channel
+        .versions
+        .iter()
+        .filter(|v| v.major == major && v.minor == minor)
+        .cloned()
+        .collect()
+}
+
+/// Copy versions of `major.minor` between channels of the same weight for minors `minor` and `minor + 1`,
+/// as hack/backfill.py does, so that both channels agree. Added versions are inserted in order,
+/// other entries are left in place
+pub fn run(data_dir: &Path, dry_run: bool) -> Fallible<()> {
+    let mut loaded: BTreeMap<ChannelKey, Loaded> = BTreeMap::new();
+    for path in fmt::channel_files(data_dir)? {
+        let contents = read_to_string(&path).context(format!("Reading {:?}", path))?;
+        let channel: Channel =
+            serde_yaml::from_str(&contents).context(format!("Parsing {:?}", path))?;
+        let file = ChannelFile::parse(&contents).context(format!("Parsing {:?}", path))?;
+        let (weight, version) = channels::split_name(&channel.name)
+            .ok_or_else(|| anyhow::anyhow!("Cannot parse channel name {:?}", channel.name))?;
+        let mut parts = version.split('.').map(str::parse::<u64>);
+        let (major, minor) = match (parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor))) => (major, minor),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Cannot parse channel name {:?}",
+                    channel.name
+                ))
+            }
+        };
+        let key = (weight.to_string(), major, minor);
+        if let Some(other) = loaded.get(&key) {
+            return Err(anyhow::anyhow!(
+                "{:?} and {:?} both define channel {}",
+                other.path,
+                path,
+                channel.name
+            ));
+        }
+        loaded.insert(
+            key,
+            Loaded {
+                path,
+                channel,
+                file,
+                added: vec![],
+            },
+        );
+    }
+
+    let pairs: Vec<(ChannelKey, ChannelKey)> = loaded
+        .keys()
+        .map(|(weight, major, minor)| {
+            (
+                (weight.clone(), *major, *minor),
+                (weight.clone(), *major, minor + 1),
+            )
+        })
+        .filter(|(_, next)| loaded.contains_key(next))
+        .collect();
+
+    for (current, next) in pairs.iter() {
+        let (_, major, minor) = *current;
+        let from_next = minor_versions(&loaded[next], major, minor);
+        let from_current = minor_versions(&loaded[current], major, minor);
+        for (key, versions) in [(current, from_next), (next, from_current)].iter() {
+            let target = loaded.get_mut(key).expect("channel was loaded");
+            for v in versions.iter() {
+                if target.covers(v) {
+                    continue;
+                }
+                // Adding the version without build metadata would widen the entry to all architectures
+                if v.build.is_empty() && target.lists_arch_specific(v) {
+                    println!(
+                        "Skipping {} for {}, which lists it for specific architectures only",
+                        v, target.channel.name
+                    );
+                    continue;
+                }
+                target.added.push(v.clone());
+            }
+        }
+    }
+
+    for (key, target) in loaded.iter_mut() {
+        if target.added.is_empty() {
+            continue;
+        }
+        let before = target.file.render();
+        for v in target.added.iter() {
+            target.file.insert(v.clone(), key.2);
+        }
+        let after = target.file.render();
+        let name = target.path.strip_prefix(data_dir).unwrap_or(&target.path);
+        print!(
+            "{}",
+            unified_diff(&name.display().to_string(), &before, &after)
+        );
+        if dry_run {
+            continue;
+        }
+        write(&target.path, after).context(format!("Writing {:?}", target.path))?;
+    }
+    Ok(())
+}
+
+/// Unified diff of two versions of a file, computed from their longest common subsequence of lines
+fn unified_diff(name: &str, before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                max(lcs[i + 1][j], lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Old line index, new line index, marker and text of every line
+    let mut edits: Vec<(usize, usize, char, &str)> = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push((i, j, ' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            edits.push((i, j, '+', new[j]));
+            j += 1;
+        } else {
+            edits.push((i, j, '-', old[i]));
+            i += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..edits.len()).filter(|&k| edits[k].2 != ' ').collect();
+    let mut out = String::new();
+    if changed.is_empty() {
+        return out;
+    }
+    out.push_str(&format!("--- a/{}\n+++ b/{}\n", name, name));
+    let mut k = 0;
+    while k < changed.len() {
+        let start = changed[k].saturating_sub(DIFF_CONTEXT);
+        let mut last = changed[k];
+        while k < changed.len() && changed[k] <= last + 2 * DIFF_CONTEXT {
+            last = changed[k];
+            k += 1;
+        }
+        let hunk = &edits[start..min(last + DIFF_CONTEXT + 1, edits.len())];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk[0].0 + 1,
+            hunk.iter().filter(|e| e.2 != '+').count(),
+            hunk[0].1 + 1,
+            hunk.iter().filter(|e| e.2 != '-').count()
+        ));
+        for (_, _, marker, line) in hunk {
+            out.push_str(&format!("{}{}\n", marker, line));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_shows_inserted_lines_with_context() {
+        let before = "versions:\n- 4.6.1\n- 4.6.2\n- 4.6.3\n- 4.6.4\n- 4.6.6\n- 4.6.7\n";
+        let after = "versions:\n- 4.6.1\n- 4.6.2\n- 4.6.3\n- 4.6.4\n- 4.6.5\n- 4.6.6\n- 4.6.7\n";
+        assert_eq!(
+            unified_diff("channels/stable-4.6.yaml", before, after),
+            concat!(
+                "--- a/channels/stable-4.6.yaml\n",
+                "+++ b/channels/stable-4.6.yaml\n",
+                "@@ -3,5 +3,6 @@\n",
+                " - 4.6.2\n",
+                " - 4.6.3\n",
+                " - 4.6.4\n",
+                "+- 4.6.5\n",
+                " - 4.6.6\n",
+                " - 4.6.7\n",
+            )
+        );
+    }
+
+    #[test]
+    fn unified_diff_is_empty_without_changes() {
+        assert_eq!(unified_diff("a", "x\ny\n", "x\ny\n"), "");
+    }
+
+    #[test]
+    fn channels_with_the_same_name_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let channels = dir.path().join("channels");
+        std::fs::create_dir(&channels).unwrap();
+        for file in &["stable-4.6.yaml", "stable-4.6-copy.yaml"] {
+            write(
+                channels.join(file),
+                "name: stable-4.6\nversions:\n- 4.6.1\n",
+            )
+            .unwrap();
+        }
+        let err = run(dir.path(), true).unwrap_err();
+        assert!(err.to_string().contains("both define channel stable-4.6"));
+    }
+}
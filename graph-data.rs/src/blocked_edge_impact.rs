@@ -0,0 +1,56 @@
+use crate::arch::Arch;
+use crate::graph::{Graph, GraphData};
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Edge removed by a blocked edge file
+struct Removed {
+    channel: String,
+    from: String,
+    to: String,
+    arch: Arch,
+}
+
+/// Edges removed by each blocked edge file in every channel and architecture
+fn removed_edges(graph: &Graph, data: &GraphData) -> BTreeMap<PathBuf, Vec<Removed>> {
+    let mut result: BTreeMap<PathBuf, Vec<Removed>> = data
+        .blocked_edges
+        .iter()
+        .map(|b| (b.path.clone(), vec![]))
+        .collect();
+    for ((channel, arch), filtered) in graph.channel_graphs(&data.channels) {
+        for edge in filtered.edges.iter() {
+            for path in edge.blocked_by.iter() {
+                result.entry(path.clone()).or_default().push(Removed {
+                    channel: channel.clone(),
+                    from: filtered.nodes[edge.from].release.version().to_string(),
+                    to: filtered.nodes[edge.to].release.version().to_string(),
+                    arch,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Print edges removed by each blocked edge file and flag files which remove nothing
+pub fn run(graph: &Graph, data: &GraphData) {
+    let mut ineffective: Vec<PathBuf> = vec![];
+    for (path, removed) in removed_edges(graph, data) {
+        if removed.is_empty() {
+            ineffective.push(path);
+            continue;
+        }
+        println!("{}:", path.display());
+        for r in removed {
+            println!("  {}: {} -> {} ({})", r.channel, r.from, r.to, r.arch);
+        }
+    }
+    for path in ineffective {
+        println!(
+            "Warning: {} removes no edges, it is either obsolete or its regex is broken",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::graph_data_model::BlockedEdge;
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use regex::Regex;
+use semver::Version;
+use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsStr;
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+/// Blocked edge along with the file it was read from
+pub struct BlockedEdgeFile {
+    pub path: PathBuf,
+    pub edge: BlockedEdge,
+}
+
+/// Read all blocked edge files in the directory, sorted by path
+pub fn read(dir: &Path) -> Fallible<Vec<BlockedEdgeFile>> {
+    let mut paths: Vec<PathBuf> = read_dir(dir)
+        .context(format!("Reading {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+    paths
+        .into_iter()
+        .filter(|p| p.extension() == Some(OsStr::new("yaml")))
+        .map(|path| {
+            let contents = read_to_string(&path).context(format!("Reading {:?}", path))?;
+            let edge = serde_yaml::from_str(&contents).context(format!("Parsing {:?}", path))?;
+            Ok(BlockedEdgeFile { path, edge })
+        })
+        .collect()
+}
+
+/// Release name without build metadata, as matched by `from` regexes
+pub fn version_name(version: &Version) -> String {
+    let mut version = version.clone();
+    version.build.clear();
+    version.to_string()
+}
+
+/// Compile `from` as a regex which has to match the whole version
+pub fn anchored_regex(pattern: &str) -> Fallible<Regex> {
+    Regex::new(&format!("^(?:{})$", pattern)).context(format!("Compiling regex {:?}", pattern))
+}
+
+/// Escape dots between version components, as these are meant to be separators.
+/// A dot followed by a repetition is kept as a wildcard after the separator, so `4.5.*` becomes `4\.5\..*`,
+/// and a dot followed by a group or class separates the component it matches, so `4.5.(1|2)` becomes `4\.5\.(1|2)`
+fn escape_version_dots(pattern: &str) -> String {
+    let mut result = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut previous: Option<char> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                result.push(c);
+                if let Some(escaped) = chars.next() {
+                    result.push(escaped);
+                }
+                previous = None;
+                continue;
+            }
+            '.' if matches!(previous, Some(p) if p.is_ascii_digit()) => match chars.peek() {
+                Some(n) if n.is_ascii_digit() => result.push_str("\\."),
+                Some('(') | Some('[') => result.push_str("\\."),
+                Some('*') | Some('+') => result.push_str("\\.."),
+                _ => result.push(c),
+            },
+            _ => result.push(c),
+        }
+        previous = Some(c);
+    }
+    result
+}
+
+/// Releases which the pattern matches only because dots between version components are unescaped
+fn unescaped_matches<'a>(
+    pattern: &str,
+    matched: &BTreeSet<&'a String>,
+) -> Fallible<Vec<&'a String>> {
+    let escaped = escape_version_dots(pattern);
+    if escaped == pattern {
+        return Ok(vec![]);
+    }
+    let escaped_regex = anchored_regex(&escaped)?;
+    Ok(matched
+        .iter()
+        .filter(|v| !escaped_regex.is_match(v))
+        .cloned()
+        .collect())
+}
+
+/// Verify that `from` regexes compile and match known releases
+pub fn verify(edges: &[BlockedEdgeFile], known_versions: &HashSet<Version>) -> Fallible<()> {
+    println!("Verifying blocked edge regexes");
+    let known_names: BTreeSet<String> = known_versions.iter().map(version_name).collect();
+
+    let mut errors: Vec<String> = vec![];
+    for file in edges {
+        let pattern = file.edge.from.as_str();
+        let regex = match anchored_regex(pattern) {
+            Ok(r) => r,
+            Err(e) => {
+                errors.push(format!("{:?}: {:#}", file.path, e));
+                continue;
+            }
+        };
+        let matched: BTreeSet<&String> =
+            known_names.iter().filter(|v| regex.is_match(v)).collect();
+        if matched.is_empty() {
+            errors.push(format!(
+                "{:?}: from {:?} does not match any release in channels",
+                file.path, pattern
+            ));
+            continue;
+        }
+
+        let extra = unescaped_matches(pattern, &matched)?;
+        if !extra.is_empty() {
+            println!(
+                "Warning: {:?}: from {:?} has unescaped dots and also matches {:?}, which {:?} would not",
+                file.path,
+                pattern,
+                extra,
+                escape_version_dots(pattern)
+            );
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Invalid blocked edges: {:#?}", errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dots_before_wildcards_become_separators() {
+        assert_eq!(escape_version_dots("4.5.*"), "4\\.5\\..*");
+        assert_eq!(escape_version_dots("4.5.+"), "4\\.5\\..+");
+    }
+
+    #[test]
+    fn escaped_patterns_are_unchanged() {
+        assert_eq!(escape_version_dots("4\\.5\\..*"), "4\\.5\\..*");
+        assert_eq!(escape_version_dots(".*"), ".*");
+    }
+
+    #[test]
+    fn dots_before_groups_are_escaped() {
+        assert_eq!(escape_version_dots("4.5.(1|2)"), "4\\.5\\.(1|2)");
+        assert_eq!(escape_version_dots("4.5.[0-9]+"), "4\\.5\\.[0-9]+");
+    }
+
+    #[test]
+    fn over_matching_patterns_are_reported() {
+        let names: Vec<String> = vec!["4.5.1".to_string(), "4.50.1".to_string()];
+        let matched: BTreeSet<&String> = names.iter().collect();
+        assert_eq!(
+            unescaped_matches("4.5.*", &matched).unwrap(),
+            vec![&names[1]]
+        );
+        assert!(unescaped_matches("4\\.5\\..*", &matched)
+            .unwrap()
+            .is_empty());
+    }
+}
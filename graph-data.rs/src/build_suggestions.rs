@@ -0,0 +1,130 @@
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use semver::Version;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+pub static BUILD_SUGGESTIONS_DIR: &str = "build-suggestions";
+
+/// Suggestions are set by default or overridden for a specific architecture
+#[derive(Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKey {
+    Default,
+    Amd64,
+    Ppc64le,
+    S390x,
+}
+
+impl SuggestionKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionKey::Default => "default",
+            SuggestionKey::Amd64 => "amd64",
+            SuggestionKey::Ppc64le => "ppc64le",
+            SuggestionKey::S390x => "s390x",
+        }
+    }
+}
+
+/// Range of versions suggested as update sources for a new build
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildSuggestion {
+    pub minor_min: Version,
+    pub minor_max: Version,
+    pub minor_block_list: Vec<Version>,
+    pub z_min: Version,
+    pub z_max: Version,
+    pub z_block_list: Vec<Version>,
+}
+
+/// Contents of a build suggestions file
+pub type BuildSuggestions = BTreeMap<SuggestionKey, BuildSuggestion>;
+
+/// Verify that min <= max and that block list entries fall in the range
+fn verify_range(name: &str, min: &Version, max: &Version, block_list: &[Version]) -> Vec<String> {
+    let mut errors = vec![];
+    if min > max {
+        errors.push(format!("{}_min {} is greater than {}_max {}", name, min, name, max));
+    }
+    for v in block_list {
+        if v < min || v > max {
+            errors.push(format!(
+                "{}_block_list entry {} is outside of range {} - {}",
+                name, v, min, max
+            ));
+        }
+    }
+    errors
+}
+
+impl BuildSuggestion {
+    fn verify(&self) -> Vec<String> {
+        let mut errors = verify_range(
+            "minor",
+            &self.minor_min,
+            &self.minor_max,
+            &self.minor_block_list,
+        );
+        errors.extend(verify_range(
+            "z",
+            &self.z_min,
+            &self.z_max,
+            &self.z_block_list,
+        ));
+        errors
+    }
+}
+
+/// Parse a build suggestions file
+fn read(path: &Path) -> Fallible<BuildSuggestions> {
+    let contents = read_to_string(path).context(format!("Reading {:?}", path))?;
+    serde_yaml::from_str(&contents).context(format!("Parsing {:?}", path))
+}
+
+/// Verify all build suggestion files in the data directory
+pub fn run(data_dir: &Path) -> Fallible<()> {
+    println!("Verifying build suggestion files are valid");
+    let dir = data_dir.join(BUILD_SUGGESTIONS_DIR);
+    let mut paths: Vec<PathBuf> = read_dir(&dir)
+        .context(format!("Reading {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+
+    let mut errors: Vec<String> = vec![];
+    for path in paths {
+        if path.extension() != Some(OsStr::new("yaml")) {
+            errors.push(format!("{:?}: unexpected file extension", path));
+            continue;
+        }
+        let suggestions = match read(&path) {
+            Ok(s) => s,
+            Err(e) => {
+                errors.push(format!("{:#}", e));
+                continue;
+            }
+        };
+        if !suggestions.contains_key(&SuggestionKey::Default) {
+            errors.push(format!("{:?}: missing default suggestions", path));
+        }
+        for (key, suggestion) in suggestions.iter() {
+            errors.extend(
+                suggestion
+                    .verify()
+                    .into_iter()
+                    .map(|e| format!("{:?}: {}: {}", path, key.as_str(), e)),
+            );
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Invalid build suggestions: {:#?}", errors))
+    }
+}
@@ -0,0 +1,233 @@
+use crate::arch::{Arch, ArchVersion};
+
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::graph_data_model::Channel;
+
+use anyhow::Result as Fallible;
+use semver::Version;
+use std::collections::{BTreeMap, HashSet};
+
+/// Channel weights where versions of the first one must be promoted through the second one
+static PROMOTION_ORDER: &[(&str, &str)] =
+    &[("stable", "fast"), ("fast", "candidate"), ("eus", "stable")];
+
+/// Split channel name into weight and version, i.e. `stable-4.6` into `stable` and `4.6`
+pub fn split_name(name: &str) -> Option<(&str, &str)> {
+    let separator = name.rfind('-')?;
+    Some((&name[..separator], &name[separator + 1..]))
+}
+
+/// Minor version of the channel, i.e. 6 for `stable-4.6`
+pub fn channel_minor(name: &str) -> Option<u64> {
+    let (_, version) = split_name(name)?;
+    version.split('.').nth(1)?.parse().ok()
+}
+
+/// Sort key for versions in a channel, matching `version_key` in hack/backfill.py:
+/// versions of the channel minor ascending, versions of other minors by descending patch
+pub fn version_key(version: &Version, channel_minor: u64) -> (u64, u64, i64, String) {
+    let patch = if version.minor == channel_minor {
+        version.patch as i64
+    } else {
+        -(version.patch as i64)
+    };
+    (version.major, version.minor, patch, version.to_string())
+}
+
+/// Verify that versions in each channel are also present in the channel they are promoted from
+pub fn verify_promotion(channels: &[Channel]) -> Fallible<()> {
+    println!("Verifying channel promotion order");
+    let by_name: BTreeMap<&str, &Channel> = channels.iter().map(|c| (c.name.as_str(), c)).collect();
+
+    let mut errors: Vec<String> = vec![];
+    for (name, channel) in by_name.iter() {
+        let (weight, version) = match split_name(name) {
+            Some(s) => s,
+            None => continue,
+        };
+        for (subset, superset) in PROMOTION_ORDER {
+            if weight != *subset {
+                continue;
+            }
+            let superset_name = format!("{}-{}", superset, version);
+            let superset_channel = match by_name.get(superset_name.as_str()) {
+                Some(c) => c,
+                None => continue,
+            };
+            let superset_versions: Vec<ArchVersion> = superset_channel
+                .versions
+                .iter()
+                .map(ArchVersion::parse)
+                .collect::<Fallible<_>>()?;
+            // Versions without build metadata must be promoted for all architectures
+            let mut missing: Vec<String> = vec![];
+            for v in channel.versions.iter() {
+                let v = ArchVersion::parse(v)?;
+                let arches = match v.arch {
+                    Some(arch) => vec![arch],
+                    None => Arch::ALL.to_vec(),
+                };
+                let required = arches.len();
+                let missing_arches: Vec<Arch> = arches
+                    .into_iter()
+                    .filter(|a| !superset_versions.iter().any(|s| s.matches(&v.version, *a)))
+                    .collect();
+                if missing_arches.len() == required {
+                    missing.push(v.to_string());
+                } else {
+                    for arch in missing_arches {
+                        missing.push(format!("{}+{}", v.version, arch));
+                    }
+                }
+            }
+            if !missing.is_empty() {
+                errors.push(format!(
+                    "{} versions missing in {}: {}",
+                    name,
+                    superset_name,
+                    missing.join(", ")
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Channel promotion errors: {:#?}", errors))
+    }
+}
+
+/// Describe the first version of the channel which is out of order, with its neighbours
+fn out_of_order(channel: &Channel, minor: u64) -> Option<String> {
+    let versions = &channel.versions;
+    let i = (1..versions.len())
+        .find(|&i| version_key(&versions[i - 1], minor) > version_key(&versions[i], minor))?;
+    let next = versions
+        .get(i + 1)
+        .map_or_else(|| "end of list".to_string(), |v| v.to_string());
+    Some(format!(
+        "{}: version {} is out of order, found between {} and {}",
+        channel.name,
+        versions[i],
+        versions[i - 1],
+        next
+    ))
+}
+
+/// Verify that channels have no duplicate versions and report versions which are out of order
+pub fn verify_order(channels: &[Channel]) -> Fallible<()> {
+    println!("Verifying channel versions are ordered");
+    let mut errors: Vec<String> = vec![];
+    for channel in channels {
+        let mut seen: HashSet<String> = HashSet::new();
+        for v in channel.versions.iter() {
+            if !seen.insert(v.to_string()) {
+                errors.push(format!("{}: duplicate version {}", channel.name, v));
+            }
+        }
+
+        let minor = match channel_minor(&channel.name) {
+            Some(m) => m,
+            None => {
+                errors.push(format!("{}: cannot parse minor version", channel.name));
+                continue;
+            }
+        };
+        if let Some(warning) = out_of_order(channel, minor) {
+            println!("Warning: {}", warning);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Channel order errors: {:#?}", errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(name: &str, versions: &[&str]) -> Channel {
+        serde_yaml::from_str(&format!(
+            "name: {}\nversions: [{}]",
+            name,
+            versions.join(", ")
+        ))
+        .unwrap()
+    }
+
+    fn promotion_errors(channels: &[Channel]) -> String {
+        format!("{}", verify_promotion(channels).unwrap_err())
+    }
+
+    #[test]
+    fn promoted_versions_pass() {
+        let channels = vec![
+            channel("candidate-4.6", &["4.6.0", "4.6.1"]),
+            channel("fast-4.6", &["4.6.1"]),
+            channel("stable-4.6", &["4.6.1+amd64"]),
+        ];
+        verify_promotion(&channels).unwrap();
+    }
+
+    #[test]
+    fn versions_missing_in_superset_are_reported() {
+        let channels = vec![
+            channel("candidate-4.6", &["4.6.0"]),
+            channel("fast-4.6", &["4.6.0", "4.6.1"]),
+        ];
+        let errors = promotion_errors(&channels);
+        assert!(errors.contains("fast-4.6 versions missing in candidate-4.6: 4.6.1"));
+    }
+
+    #[test]
+    fn versions_promoted_for_one_arch_report_missing_arches() {
+        let channels = vec![
+            channel("fast-4.6", &["4.6.1+amd64"]),
+            channel("stable-4.6", &["4.6.1"]),
+        ];
+        let errors = promotion_errors(&channels);
+        assert!(
+            errors.contains("stable-4.6 versions missing in fast-4.6: 4.6.1+ppc64le, 4.6.1+s390x"),
+            "{}",
+            errors
+        );
+    }
+
+    #[test]
+    fn other_minors_descending_then_channel_minor_ascending_is_ordered() {
+        let ordered = channel("stable-4.6", &["4.5.16", "4.5.15", "4.6.1", "4.6.3"]);
+        assert_eq!(out_of_order(&ordered, 6), None);
+        verify_order(&[ordered]).unwrap();
+    }
+
+    #[test]
+    fn out_of_order_version_is_reported_with_neighbours() {
+        let misplaced_other_minor = channel("stable-4.6", &["4.5.15", "4.5.16", "4.6.1"]);
+        assert_eq!(
+            out_of_order(&misplaced_other_minor, 6).unwrap(),
+            "stable-4.6: version 4.5.16 is out of order, found between 4.5.15 and 4.6.1"
+        );
+
+        let misplaced_last = channel("stable-4.6", &["4.6.1", "4.6.3", "4.6.2"]);
+        assert_eq!(
+            out_of_order(&misplaced_last, 6).unwrap(),
+            "stable-4.6: version 4.6.2 is out of order, found between 4.6.3 and end of list"
+        );
+        // Ordering is only a warning
+        verify_order(&[misplaced_last]).unwrap();
+    }
+
+    #[test]
+    fn duplicate_versions_are_rejected() {
+        let duplicated = channel("stable-4.6", &["4.6.1", "4.6.1"]);
+        let errors = format!("{}", verify_order(&[duplicated]).unwrap_err());
+        assert!(
+            errors.contains("stable-4.6: duplicate version 4.6.1"),
+            "{}",
+            errors
+        );
+    }
+}
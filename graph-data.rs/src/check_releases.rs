@@ -1,3 +1,7 @@
+use crate::arch::{self, Arch, ArchVersion};
+use crate::scrape_cache;
+
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::graph_data_model::Channel;
 use cincinnati::plugins::internal::release_scrape_dockerv2::plugin;
 use cincinnati::plugins::internal::release_scrape_dockerv2::registry;
 use cincinnati::Release;
@@ -5,42 +9,88 @@ use cincinnati::Release;
 use anyhow::Context;
 use anyhow::Result as Fallible;
 use semver::Version;
-use std::collections::HashSet;
-use std::str::FromStr;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::path::Path;
+
+/// Release along with update edges declared in its release metadata
+#[derive(Clone, Debug)]
+pub struct ScrapedRelease {
+    pub release: Release,
+    /// Release version without build metadata
+    pub version: Version,
+    pub arch: Arch,
+    pub previous: Vec<Version>,
+    pub next: Vec<Version>,
+}
 
-pub async fn run(found_versions: &HashSet<Version>) -> Fallible<Vec<Release>> {
-    let settings = plugin::ReleaseScrapeDockerv2Settings::default();
-    let cache = registry::cache::new();
+/// Fetch release metadata of all images in the repository
+pub async fn scrape(
+    settings: &plugin::ReleaseScrapeDockerv2Settings,
+    cache_dir: Option<&Path>,
+) -> Fallible<Vec<registry::Release>> {
+    let cache = match cache_dir {
+        Some(dir) => scrape_cache::load(dir).await?,
+        None => registry::cache::new(),
+    };
     let registry = registry::Registry::try_from_str(&settings.registry)
         .context(format!("Parsing {} as Registry", &settings.registry))?;
 
-    println!("Scraping Quay registry");
-    let released_metadata: Vec<Release> = registry::fetch_releases(
+    println!("Scraping {} registry", &settings.registry);
+    let releases = registry::fetch_releases(
         &registry,
         &settings.repository,
         settings.username.as_ref().map(String::as_ref),
         settings.password.as_ref().map(String::as_ref),
-        cache,
+        cache.clone(),
         &settings.manifestref_key,
         settings.fetch_concurrency,
     )
     .await
-    .context("failed to fetch all release metadata")?
-    .into_iter()
-    .map(|r| r.into())
-    .collect();
-
-    let released_versions: HashSet<Version> = released_metadata
-        .clone()
-        .into_iter()
-        .map(|m| Version::from_str(m.version()).unwrap())
-        .collect();
+    .context("failed to fetch all release metadata")?;
+    if let Some(dir) = cache_dir {
+        let sources: HashSet<&str> = releases.iter().map(|r| r.source.as_str()).collect();
+        scrape_cache::save(dir, &cache, &sources).await?;
+    }
+    Ok(releases)
+}
+
+/// Verify that all releases mentioned in graph data were scraped.
+/// Releases with an unknown architecture are skipped with a warning
+pub fn run(
+    releases: Vec<registry::Release>,
+    found_versions: &HashSet<ArchVersion>,
+) -> Fallible<Vec<ScrapedRelease>> {
+    let mut released_metadata: Vec<ScrapedRelease> = vec![];
+    for r in releases {
+        let previous = r.metadata.previous.clone();
+        let next = r.metadata.next.clone();
+        let release: Release = r.into();
+        match arch::release_arch(&release) {
+            Ok((version, arch)) => released_metadata.push(ScrapedRelease {
+                release,
+                version,
+                arch,
+                previous,
+                next,
+            }),
+            Err(e) => println!("Warning: skipping release {}: {:#}", release.version(), e),
+        }
+    }
 
     println!("Verifying all releases are uploaded");
-    let missing_versions: HashSet<&Version> =
-        found_versions.difference(&released_versions).collect();
+    let mut missing_versions: Vec<String> = found_versions
+        .iter()
+        .filter(|v| {
+            !released_metadata
+                .iter()
+                .any(|r| v.matches(&r.version, r.arch))
+        })
+        .map(ArchVersion::to_string)
+        .collect();
+    missing_versions.sort();
     if missing_versions.is_empty() {
-        Ok(released_metadata.clone())
+        Ok(released_metadata)
     } else {
         Err(anyhow::anyhow!(
             "Missing the following versions in scraped images: {:?}",
@@ -48,3 +98,113 @@ pub async fn run(found_versions: &HashSet<Version>) -> Fallible<Vec<Release>> {
         ))
     }
 }
+
+/// Architectures which have a payload for a channel entry
+#[derive(Serialize)]
+pub struct Coverage {
+    channel: String,
+    version: String,
+    arches: Vec<Arch>,
+    /// Architectures the channel requires, but which have no payload
+    missing: Vec<Arch>,
+}
+
+/// Report which architectures have a payload for every version in every channel.
+/// Channels listed in `multi_arch` need payloads for all architectures,
+/// other channels only for the architecture named in the version build metadata
+pub fn report_coverage(
+    channels: &[Channel],
+    releases: &[ScrapedRelease],
+    multi_arch: &[String],
+    json: Option<&Path>,
+) -> Fallible<()> {
+    println!("Checking release coverage of architectures");
+    for name in multi_arch {
+        if !channels.iter().any(|c| c.name == *name) {
+            return Err(anyhow::anyhow!("Multi-arch channel {} not found", name));
+        }
+    }
+    let mut report: Vec<Coverage> = vec![];
+    for channel in channels {
+        let required_all = multi_arch.contains(&channel.name);
+        for v in channel.versions.iter() {
+            let version = ArchVersion::parse(v).context(format!("Channel {}", channel.name))?;
+            let arches: BTreeSet<Arch> = releases
+                .iter()
+                .filter(|r| version.matches(&r.version, r.arch))
+                .map(|r| r.arch)
+                .collect();
+            let required: Vec<Arch> = match version.arch {
+                Some(arch) => vec![arch],
+                None if required_all => Arch::ALL.to_vec(),
+                None => vec![],
+            };
+            report.push(Coverage {
+                channel: channel.name.clone(),
+                version: version.to_string(),
+                missing: required
+                    .into_iter()
+                    .filter(|a| !arches.contains(a))
+                    .collect(),
+                arches: arches.into_iter().collect(),
+            });
+        }
+    }
+
+    let channel_width = report.iter().map(|c| c.channel.len()).max().unwrap_or(0);
+    let version_width = report.iter().map(|c| c.version.len()).max().unwrap_or(0);
+    let mut header = format!(
+        "{:cw$}  {:vw$}",
+        "channel",
+        "version",
+        cw = channel_width,
+        vw = version_width
+    );
+    for arch in Arch::ALL.iter() {
+        header.push_str(&format!("  {:7}", arch.as_str()));
+    }
+    println!("{}", header.trim_end());
+    for c in report.iter() {
+        let mut row = format!(
+            "{:cw$}  {:vw$}",
+            c.channel,
+            c.version,
+            cw = channel_width,
+            vw = version_width
+        );
+        for arch in Arch::ALL.iter() {
+            let mark = if c.arches.contains(arch) {
+                "yes"
+            } else if c.missing.contains(arch) {
+                "MISSING"
+            } else {
+                "-"
+            };
+            row.push_str(&format!("  {:7}", mark));
+        }
+        println!("{}", row.trim_end());
+    }
+
+    if let Some(path) = json {
+        let contents = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, contents).context(format!("Writing {:?}", path))?;
+        println!("Wrote release coverage report to {:?}", path);
+    }
+
+    let missing: Vec<String> = report
+        .iter()
+        .flat_map(|c| {
+            c.missing
+                .iter()
+                .map(move |a| format!("{}: {} ({})", c.channel, c.version, a))
+        })
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Missing payloads for the following architectures: {:#?}",
+            missing
+        ))
+    }
+}
@@ -1,27 +1,26 @@
+use crate::arch::{self, ArchVersion};
 use crate::gpg;
+use crate::verification_config::VerificationConfig;
 
 use anyhow::Result as Fallible;
 use anyhow::{format_err, Context};
 
 use bytes::Bytes;
 use futures::stream::{FuturesOrdered, StreamExt};
-use lazy_static::lazy_static;
 use reqwest::{Client, ClientBuilder};
-use semver::Version;
 use std::collections::HashSet;
+use std::fmt;
 use std::ops::Range;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 use url::Url;
 
 use cincinnati::plugins::prelude_plugin_impl::TryFutureExt;
 use cincinnati::Release;
-// base url for signature storage - see https://github.com/openshift/cluster-update-keys/blob/master/stores/store-openshift-official-release-mirror
-lazy_static! {
-  static ref BASE_URL: Url =
-    Url::parse("https://mirror.openshift.com/pub/openshift-v4/signatures/openshift/release/")
-      .expect("could not parse url");
-}
+// Default base url for signature storage - see https://github.com/openshift/cluster-update-keys/blob/master/stores/store-openshift-official-release-mirror
+pub static DEFAULT_STORE: &str =
+  "https://mirror.openshift.com/pub/openshift-v4/signatures/openshift/release/";
 
 // Signature file request timeout
 static DEFAULT_TIMEOUT_SECS: u64 = 30;
@@ -45,6 +44,63 @@ static SKIP_VERSIONS: &[&str] = &[
   "4.1.0+amd64",
 ];
 
+/// Location of release signatures, laid out as `sha256=<digest>/signature-N`
+#[derive(Clone, Debug)]
+pub enum SignatureStore {
+  /// HTTP(S) base URL
+  Http(Url),
+  /// Local directory, given as a path or a file:// URL
+  Directory(PathBuf),
+}
+
+impl SignatureStore {
+  /// Whether signatures can be read without network access
+  pub fn is_local(&self) -> bool {
+    matches!(self, SignatureStore::Directory(_))
+  }
+}
+
+impl FromStr for SignatureStore {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Fallible<Self> {
+    let mut url = match Url::parse(s) {
+      Ok(url) => url,
+      Err(url::ParseError::RelativeUrlWithoutBase) => {
+        return Ok(SignatureStore::Directory(PathBuf::from(s)))
+      }
+      Err(e) => return Err(format_err!("Parsing signature store {}: {}", s, e)),
+    };
+    match url.scheme() {
+      "http" | "https" => {
+        // Url::join replaces the last path segment unless the base ends with a slash
+        if !url.path().ends_with('/') {
+          url.set_path(&format!("{}/", url.path()));
+        }
+        Ok(SignatureStore::Http(url))
+      }
+      "file" => url
+        .to_file_path()
+        .map(SignatureStore::Directory)
+        .map_err(|_| format_err!("Signature store {} is not a local path", s)),
+      scheme => Err(format_err!(
+        "Unsupported signature store scheme {} in {}, expected http, https or file",
+        scheme,
+        s
+      )),
+    }
+  }
+}
+
+impl fmt::Display for SignatureStore {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SignatureStore::Http(url) => write!(f, "{}", url),
+      SignatureStore::Directory(path) => write!(f, "{}", path.display()),
+    }
+  }
+}
+
 /// Extract payload value from Release if it is a Concrete release
 fn payload_from_release(release: &Release) -> Fallible<String> {
   match release {
@@ -53,11 +109,35 @@ fn payload_from_release(release: &Release) -> Fallible<String> {
   }
 }
 
-/// Fetch signature contents by building a URL for signature store
-async fn fetch_url(client: &Client, sha: &str, i: u64) -> Fallible<Bytes> {
-  let url = BASE_URL
-    .join(format!("{}/", sha.replace(":", "=")).as_str())?
-    .join(format!("signature-{}", i).as_str())?;
+/// URL of the i-th signature of the digest in an HTTP signature store
+fn signature_url(base: &Url, sha: &str, i: u64) -> Fallible<Url> {
+  Ok(
+    base
+      .join(format!("{}/", sha.replace(":", "=")).as_str())?
+      .join(format!("signature-{}", i).as_str())?,
+  )
+}
+
+/// Fetch signature contents from the signature store
+async fn fetch_signature(
+  client: &Client,
+  store: &SignatureStore,
+  sha: &str,
+  i: u64,
+) -> Fallible<Bytes> {
+  let store = match store {
+    SignatureStore::Http(url) => url,
+    SignatureStore::Directory(dir) => {
+      let path = dir
+        .join(sha.replace(":", "="))
+        .join(format!("signature-{}", i));
+      return tokio::fs::read(&path)
+        .await
+        .map(Bytes::from)
+        .map_err(|e| format_err!("Error reading {:?} - {}", path, e));
+    }
+  };
+  let url = signature_url(store, sha, i)?;
   let res = client
     .get(url.clone())
     .send()
@@ -72,10 +152,11 @@ async fn fetch_url(client: &Client, sha: &str, i: u64) -> Fallible<Bytes> {
   }
 }
 
-/// Generate URLs for signature store and attempt to find a valid signature
+/// Try signature stores in order and attempt to find a valid signature
 async fn find_signatures_for_version(
   client: &Client,
   public_keys: &gpg::Keyring,
+  stores: &[SignatureStore],
   release: &Release,
 ) -> Fallible<()> {
   let mut errors = vec![];
@@ -85,55 +166,49 @@ async fn find_signatures_for_version(
     .last()
     .ok_or_else(|| format_err!("could not parse payload '{:?}'", payload))?;
 
-  let mut attempts = Range {
-    start: 1,
-    end: MAX_SIGNATURES,
-  };
-  loop {
-    if let Some(i) = attempts.next() {
-      match fetch_url(client, digest, i).await {
+  for store in stores {
+    let attempts = Range {
+      start: 1,
+      end: MAX_SIGNATURES,
+    };
+    for i in attempts {
+      match fetch_signature(client, store, digest, i).await {
         Ok(body) => match gpg::verify_signature(public_keys, body, digest).await {
           Ok(_) => return Ok(()),
           Err(e) => errors.push(e),
         },
         Err(e) => errors.push(e),
       }
-    } else {
-      return Err(format_err!(
-        "Failed to find signatures for {} - {}: {:#?}",
-        release.version(),
-        payload,
-        errors
-      ));
     }
   }
+  Err(format_err!(
+    "Failed to find signatures for {} - {}: {:#?}",
+    release.version(),
+    payload,
+    errors
+  ))
 }
 
 /// Iterate versions and return true if Release is included
-fn is_release_in_versions(versions: &HashSet<Version>, release: &Release) -> bool {
+fn is_release_in_versions(versions: &HashSet<ArchVersion>, release: &Release) -> bool {
   // Check that release version is not in skip list
   if SKIP_VERSIONS.contains(&release.version()) {
     return false;
   }
-  // Strip arch identifier
-  let stripped_version = release
-    .version()
-    .split("+")
-    .next()
-    .ok_or(release.version())
-    .unwrap();
-  let version = Version::from_str(stripped_version).unwrap();
-  versions.contains(&version)
+  // Versions without build metadata apply to all architectures
+  match arch::release_arch(release) {
+    Ok((version, arch)) => versions.iter().any(|v| v.matches(&version, arch)),
+    Err(_) => false,
+  }
 }
 
 pub async fn run(
   releases: &Vec<Release>,
-  found_versions: &HashSet<semver::Version>,
+  found_versions: &HashSet<ArchVersion>,
+  config: &VerificationConfig,
 ) -> Fallible<()> {
-  println!("Checking release signatures");
-
-  // Initialize keyring
-  let public_keys = gpg::load_public_keys()?;
+  let stores: Vec<String> = config.stores.iter().map(SignatureStore::to_string).collect();
+  println!("Checking release signatures in {}", stores.join(", "));
 
   // Prepare http client
   let client: Client = ClientBuilder::new()
@@ -151,7 +226,7 @@ pub async fn run(
   let results: Vec<Fallible<()>> = tracked_versions
     //Attempt to find signatures for filtered releases
     .into_iter()
-    .map(|ref r| find_signatures_for_version(&client, &public_keys, r))
+    .map(|ref r| find_signatures_for_version(&client, &config.keys, &config.stores, r))
     .collect::<FuturesOrdered<_>>()
     .collect::<Vec<Fallible<()>>>()
     .await
@@ -165,3 +240,45 @@ pub async fn run(
     Err(format_err!("Signature check errors: {:#?}", results))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  static SHA: &str = "sha256:c3cabb489081cf4b04138da9e919bd8ff61148aeea59c7a8564ed9abd5771ffb";
+
+  #[test]
+  fn http_store_without_trailing_slash_keeps_its_path() {
+    let url = match SignatureStore::from_str("https://example.com/signatures").unwrap() {
+      SignatureStore::Http(url) => url,
+      store => panic!("unexpected store {:?}", store),
+    };
+    assert_eq!(
+      signature_url(&url, SHA, 1).unwrap().as_str(),
+      "https://example.com/signatures/sha256=c3cabb489081cf4b04138da9e919bd8ff61148aeea59c7a8564ed9abd5771ffb/signature-1"
+    );
+  }
+
+  #[test]
+  fn file_url_is_a_directory() {
+    match SignatureStore::from_str("file:///srv/signatures").unwrap() {
+      SignatureStore::Directory(path) => assert_eq!(path, PathBuf::from("/srv/signatures")),
+      store => panic!("unexpected store {:?}", store),
+    }
+  }
+
+  #[test]
+  fn relative_path_is_a_directory() {
+    match SignatureStore::from_str("tests/fixtures/signatures").unwrap() {
+      SignatureStore::Directory(path) => {
+        assert_eq!(path, PathBuf::from("tests/fixtures/signatures"))
+      }
+      store => panic!("unexpected store {:?}", store),
+    }
+  }
+
+  #[test]
+  fn unsupported_scheme_is_rejected() {
+    assert!(SignatureStore::from_str("ftp://example.com/signatures/").is_err());
+  }
+}
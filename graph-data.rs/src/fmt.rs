@@ -0,0 +1,300 @@
+use crate::arch::Arch;
+use crate::channels;
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::CHANNELS_DIR;
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::{Identifier, Version};
+use std::ffi::OsStr;
+use std::fs::{read_dir, read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+lazy_static! {
+    // Version mentioned in a comment, i.e. "# no 4.1.32 because ..."
+    static ref COMMENT_VERSION: Regex =
+        Regex::new(r"\d+\.\d+\.\d+(-[0-9A-Za-z.-]*[0-9A-Za-z])?(\+[0-9A-Za-z.-]*[0-9A-Za-z])?")
+            .expect("could not compile regex");
+}
+
+/// Part of a channel file which is moved as a whole when versions are sorted
+struct Block {
+    /// Version which determines position of the block
+    key: Version,
+    /// Whether the block is a list entry or a standalone comment about a version
+    entry: bool,
+    lines: Vec<String>,
+}
+
+/// Channel file split into blocks, so that it can be reordered without losing comments.
+/// Comments which mention a version are placed where that version would be listed,
+/// other comments stay attached to the entry which follows them
+pub struct ChannelFile {
+    header: Vec<String>,
+    blocks: Vec<Block>,
+    trailer: Vec<String>,
+}
+
+/// Parse the version of a list entry, i.e. `- 4.6.1  # comment`
+fn entry_version(line: &str) -> Option<Version> {
+    let line = line.trim();
+    if !line.starts_with("- ") {
+        return None;
+    }
+    let value = line[2..]
+        .split('#')
+        .next()?
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'');
+    Version::from_str(value).ok()
+}
+
+/// Parse a version mentioned in a comment. Architecture suffixes like `4.2.14-s390x`
+/// name the architecture, not a prerelease
+fn comment_version(mentioned: &str) -> Fallible<Version> {
+    let mut version = Version::from_str(mentioned)?;
+    if let [Identifier::AlphaNumeric(pre)] = version.pre.as_slice() {
+        if Arch::from_str(pre).is_ok() {
+            version.pre.clear();
+        }
+    }
+    Ok(version)
+}
+
+impl ChannelFile {
+    pub fn parse(contents: &str) -> Fallible<Self> {
+        let mut lines = contents.lines();
+        let mut header: Vec<String> = vec![];
+        for line in &mut lines {
+            header.push(line.to_string());
+            if line.trim_end() == "versions:" {
+                break;
+            }
+        }
+
+        let mut blocks: Vec<Block> = vec![];
+        // Comments and blank lines which go with the next block
+        let mut leading: Vec<String> = vec![];
+        for line in lines {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                leading.push(line.to_string());
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                if let Some(m) = COMMENT_VERSION.find(trimmed) {
+                    // Blank lines before the comment move with it, other leading comments
+                    // stay attached to the next entry
+                    let mut lines: Vec<String> = vec![];
+                    if leading.iter().all(|l| l.trim().is_empty()) {
+                        lines.append(&mut leading);
+                    }
+                    lines.push(line.to_string());
+                    blocks.push(Block {
+                        key: comment_version(m.as_str())?,
+                        entry: false,
+                        lines,
+                    });
+                    continue;
+                }
+                match blocks.last_mut() {
+                    Some(b) if !b.entry && leading.is_empty() => b.lines.push(line.to_string()),
+                    _ => leading.push(line.to_string()),
+                }
+                continue;
+            }
+            let version = entry_version(line)
+                .ok_or_else(|| anyhow::anyhow!("Unexpected line in version list: {:?}", line))?;
+            leading.push(line.to_string());
+            blocks.push(Block {
+                key: version,
+                entry: true,
+                lines: std::mem::take(&mut leading),
+            });
+        }
+
+        Ok(ChannelFile {
+            header,
+            blocks,
+            trailer: leading,
+        })
+    }
+
+    /// Insert a version before the first block which sorts after it, leaving other blocks in place
+    pub fn insert(&mut self, version: Version, channel_minor: u64) {
+        let key = (channels::version_key(&version, channel_minor), true);
+        let position = self
+            .blocks
+            .iter()
+            .position(|b| (channels::version_key(&b.key, channel_minor), b.entry) > key)
+            .unwrap_or(self.blocks.len());
+        self.blocks.insert(
+            position,
+            Block {
+                lines: vec![format!("- {}", version)],
+                key: version,
+                entry: true,
+            },
+        );
+    }
+
+    /// Sort blocks in canonical order, comments go before the version they mention
+    pub fn sort(&mut self, channel_minor: u64) {
+        self.blocks
+            .sort_by_cached_key(|b| (channels::version_key(&b.key, channel_minor), b.entry));
+    }
+
+    pub fn render(&self) -> String {
+        let mut lines: Vec<&str> = self.header.iter().map(String::as_str).collect();
+        for block in self.blocks.iter() {
+            lines.extend(block.lines.iter().map(String::as_str));
+        }
+        lines.extend(self.trailer.iter().map(String::as_str));
+        format!("{}\n", lines.join("\n"))
+    }
+}
+
+/// List channel files in the data directory
+pub fn channel_files(data_dir: &Path) -> Fallible<Vec<PathBuf>> {
+    let dir = data_dir.join(CHANNELS_DIR);
+    let mut paths: Vec<PathBuf> = read_dir(&dir)
+        .context(format!("Reading {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|p| p.extension() == Some(OsStr::new("yaml")));
+    paths.sort();
+    Ok(paths)
+}
+
+/// Minor version of the channel stored in the file, i.e. 6 for `channels/stable-4.6.yaml`
+pub fn file_channel_minor(path: &Path) -> Fallible<u64> {
+    path.file_stem()
+        .and_then(OsStr::to_str)
+        .and_then(channels::channel_minor)
+        .ok_or_else(|| anyhow::anyhow!("Cannot parse channel minor version from {:?}", path))
+}
+
+/// Rewrite channel files in canonical order. In check mode files are not written
+/// and an error is returned if any of them is not formatted
+pub fn run(data_dir: &Path, check: bool) -> Fallible<()> {
+    let mut unformatted: Vec<PathBuf> = vec![];
+    for path in channel_files(data_dir)? {
+        let contents = read_to_string(&path).context(format!("Reading {:?}", path))?;
+        let mut file = ChannelFile::parse(&contents).context(format!("Parsing {:?}", path))?;
+        file.sort(file_channel_minor(&path)?);
+        let formatted = file.render();
+        if formatted == contents {
+            continue;
+        }
+        if check {
+            println!("{:?} is not formatted", path);
+        } else {
+            println!("Formatting {:?}", path);
+            write(&path, formatted).context(format!("Writing {:?}", path))?;
+        }
+        unformatted.push(path);
+    }
+
+    if check && !unformatted.is_empty() {
+        Err(anyhow::anyhow!(
+            "{} channel files are not formatted, run `fmt` to fix them",
+            unformatted.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static UNSORTED: &str = "\
+name: stable-4.2
+versions:
+- 4.2.16
+# not 4.2.14-s390x because of https://bugzilla.redhat.com/show_bug.cgi?id=1789260
+# not 4.2.15 because 4.2.16 was built with the same errata URI
+- 4.2.14
+# - 4.2.11 failed to run tests, we never officially released it, but we accidentally
+# put it in a channel! (same for s390x)   Now we shouldn't pull it, just in case
+# someone is on it
+- 4.2.12
+
+- 4.2.13
+- 4.1.31
+# no 4.1.32 because we didn't cut a release in the week after 4.1.31
+- 4.1.34
+";
+
+    static SORTED: &str = "\
+name: stable-4.2
+versions:
+- 4.1.34
+# no 4.1.32 because we didn't cut a release in the week after 4.1.31
+- 4.1.31
+# - 4.2.11 failed to run tests, we never officially released it, but we accidentally
+# put it in a channel! (same for s390x)   Now we shouldn't pull it, just in case
+# someone is on it
+- 4.2.12
+
+- 4.2.13
+# not 4.2.14-s390x because of https://bugzilla.redhat.com/show_bug.cgi?id=1789260
+- 4.2.14
+# not 4.2.15 because 4.2.16 was built with the same errata URI
+- 4.2.16
+";
+
+    #[test]
+    fn render_keeps_comments_and_blank_lines() {
+        let file = ChannelFile::parse(UNSORTED).unwrap();
+        assert_eq!(file.render(), UNSORTED);
+    }
+
+    #[test]
+    fn sort_moves_comments_with_versions() {
+        let mut file = ChannelFile::parse(UNSORTED).unwrap();
+        file.sort(2);
+        assert_eq!(file.render(), SORTED);
+    }
+
+    #[test]
+    fn sort_is_idempotent() {
+        let mut file = ChannelFile::parse(SORTED).unwrap();
+        file.sort(2);
+        assert_eq!(file.render(), SORTED);
+    }
+
+    #[test]
+    fn comment_arch_suffix_is_not_a_prerelease() {
+        assert_eq!(
+            comment_version("4.2.14-s390x").unwrap(),
+            Version::from_str("4.2.14").unwrap()
+        );
+        assert_eq!(
+            comment_version("4.3.0-rc.0").unwrap(),
+            Version::from_str("4.3.0-rc.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn insert_places_version_in_order() {
+        let mut file = ChannelFile::parse(SORTED).unwrap();
+        file.insert(Version::from_str("4.2.15").unwrap(), 2);
+        assert!(file.render().contains(
+            "# not 4.2.15 because 4.2.16 was built with the same errata URI\n- 4.2.15\n- 4.2.16\n"
+        ));
+    }
+
+    #[test]
+    fn insert_keeps_other_blocks_in_place() {
+        let mut file = ChannelFile::parse(UNSORTED).unwrap();
+        file.insert(Version::from_str("4.1.33").unwrap(), 2);
+        let rendered = file.render();
+        let without_inserted: Vec<&str> = rendered.lines().filter(|l| *l != "- 4.1.33").collect();
+        assert_eq!(rendered.lines().count(), without_inserted.len() + 1);
+        assert_eq!(format!("{}\n", without_inserted.join("\n")), UNSORTED);
+    }
+}
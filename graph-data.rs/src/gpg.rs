@@ -6,13 +6,16 @@ use bytes::Bytes;
 use serde::Deserialize;
 use serde_json;
 use std::fs::{read_dir, File};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
 
 use pgp::composed::message::Message;
 use pgp::composed::signed_key::SignedPublicKey;
+use pgp::types::KeyTrait;
 use pgp::Deserializable;
 
-// Location of public keys
-static PUBKEYS_DIR: &str = "/usr/local/share/public-keys/";
+// Default location of public keys
+pub static DEFAULT_KEYRING: &str = "/usr/local/share/public-keys/";
 
 // Signature format
 #[derive(Deserialize)]
@@ -34,22 +37,79 @@ struct Signature {
 /// Keyring is a collection of public keys
 pub type Keyring = Vec<SignedPublicKey>;
 
-/// Create a Keyring from a dir of public keys
-pub fn load_public_keys() -> Fallible<Keyring> {
+/// Fingerprint and user IDs of the key
+fn describe_key(key: &SignedPublicKey) -> String {
+  let fingerprint: Vec<String> = key
+    .fingerprint()
+    .iter()
+    .map(|b| format!("{:02X}", b))
+    .collect();
+  let users: Vec<&str> = key.details.users.iter().map(|u| u.id.id()).collect();
+  format!("{} {:?}", fingerprint.join(""), users)
+}
+
+/// Header of an armored public key block
+static ARMOR_HEADER: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----";
+
+/// Parse and verify all public keys in armored blocks. from_armor_many stops
+/// after the first block, so each block is parsed on its own
+pub fn read_public_keys<R: Read>(mut reader: R, name: &str) -> Fallible<Keyring> {
+  let mut contents = String::new();
+  reader
+    .read_to_string(&mut contents)
+    .context(format!("Reading {}", name))?;
+  let starts: Vec<usize> = contents
+    .match_indices(ARMOR_HEADER)
+    .map(|(i, _)| i)
+    .collect();
+
+  let mut result: Keyring = vec![];
+  for (n, start) in starts.iter().enumerate() {
+    let end = starts.get(n + 1).cloned().unwrap_or(contents.len());
+    let block = Cursor::new(&contents.as_bytes()[*start..end]);
+    let (keys, _) = SignedPublicKey::from_armor_many(block)
+      .context(format!("Parsing block {} of {}", n + 1, name))?;
+    for key in keys {
+      let pubkey = key.context(format!("Parsing block {} of {}", n + 1, name))?;
+      if let Err(err) = pubkey.verify() {
+        return Err(format_err!("Verifying key in {}: {:?}", name, err));
+      }
+      println!("Loaded public key {} from {}", describe_key(&pubkey), name);
+      result.push(pubkey);
+    }
+  }
+  if result.is_empty() {
+    return Err(format_err!("No public keys found in {}", name));
+  }
+  Ok(result)
+}
+
+fn read_public_keys_file(path: &Path) -> Fallible<Keyring> {
+  let name = path.display().to_string();
+  let file = File::open(path).context(format!("Reading {}", name))?;
+  read_public_keys(file, &name)
+}
+
+/// Create a Keyring from armored files and dirs of them. Files may contain several keys
+pub fn load_public_keys(paths: &[PathBuf]) -> Fallible<Keyring> {
   let mut result: Keyring = vec![];
-  for entry in read_dir(PUBKEYS_DIR).context("Reading public keys dir")? {
-    let path = &entry?.path();
-    let path_str = match path.to_str() {
-      None => continue,
-      Some(p) => p,
-    };
-    let file = File::open(path).context(format!("Reading {}", path_str))?;
-    let (pubkey, _) =
-      SignedPublicKey::from_armor_single(file).context(format!("Parsing {}", path_str))?;
-    match pubkey.verify() {
-      Err(err) => return Err(format_err!("{:?}", err)),
-      Ok(_) => result.push(pubkey),
-    };
+  for path in paths {
+    if !path.is_dir() {
+      result.extend(read_public_keys_file(path)?);
+      continue;
+    }
+    let mut files: Vec<PathBuf> = read_dir(path)
+      .context(format!("Reading public keys dir {:?}", path))?
+      .map(|entry| entry.map(|e| e.path()))
+      .collect::<Result<_, _>>()?;
+    files.retain(|p| p.is_file());
+    files.sort();
+    for file in files.iter() {
+      result.extend(read_public_keys_file(file)?);
+    }
+  }
+  if result.is_empty() {
+    return Err(format_err!("No public keys found in {:?}", paths));
   }
   Ok(result)
 }
@@ -83,3 +143,34 @@ pub async fn verify_signature(
     ))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  static RELEASE_KEY: &str = include_str!("../public-keys/verifier-public-key-redhat-release");
+  static BETA_KEY: &str = include_str!("../public-keys/verifier-public-key-redhat-beta-2");
+
+  #[test]
+  fn single_block_is_read() {
+    let keys = read_public_keys(RELEASE_KEY.as_bytes(), "release").unwrap();
+    assert_eq!(keys.len(), 1);
+  }
+
+  #[test]
+  fn all_blocks_of_a_bundle_are_read() {
+    let bundle = format!("{}\n{}", RELEASE_KEY, BETA_KEY);
+    let keys = read_public_keys(bundle.as_bytes(), "bundle").unwrap();
+    let fingerprints: Vec<Vec<u8>> = keys.iter().map(|k| k.fingerprint()).collect();
+    let expected: Vec<Vec<u8>> = [RELEASE_KEY, BETA_KEY]
+      .iter()
+      .map(|k| read_public_keys(k.as_bytes(), "key").unwrap()[0].fingerprint())
+      .collect();
+    assert_eq!(fingerprints, expected);
+  }
+
+  #[test]
+  fn input_without_keys_is_rejected() {
+    assert!(read_public_keys("no keys here".as_bytes(), "empty").is_err());
+  }
+}
@@ -0,0 +1,246 @@
+use crate::arch::{Arch, ArchVersion};
+use crate::blocked_edges::{self, BlockedEdgeFile};
+use crate::check_releases::ScrapedRelease;
+use crate::fmt;
+use crate::raw_metadata::{self, Annotation, Overrides};
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::graph_data_model::Channel;
+use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::BLOCKED_EDGES_DIR;
+use cincinnati::Release;
+use semver::Version;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Graph data read from the data directory
+pub struct GraphData {
+    pub channels: Vec<Channel>,
+    pub blocked_edges: Vec<BlockedEdgeFile>,
+    pub overrides: BTreeMap<Version, Overrides>,
+}
+
+impl GraphData {
+    pub fn read(data_dir: &Path) -> Fallible<Self> {
+        let mut channels: Vec<Channel> = vec![];
+        for path in fmt::channel_files(data_dir)? {
+            let contents = read_to_string(&path).context(format!("Reading {:?}", path))?;
+            channels.push(serde_yaml::from_str(&contents).context(format!("Parsing {:?}", path))?);
+        }
+        Ok(GraphData {
+            channels,
+            blocked_edges: blocked_edges::read(&data_dir.join(BLOCKED_EDGES_DIR))?,
+            overrides: raw_metadata::read(data_dir)?,
+        })
+    }
+
+    pub fn channel(&self, name: &str) -> Fallible<&Channel> {
+        self.channels
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Channel {} not found", name))
+    }
+}
+
+/// Release in the graph
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub release: Release,
+    /// Release version without build metadata
+    pub version: Version,
+    pub arch: Arch,
+}
+
+impl Node {
+    fn new(scraped: &ScrapedRelease) -> Self {
+        Node {
+            release: scraped.release.clone(),
+            version: scraped.version.clone(),
+            arch: scraped.arch,
+        }
+    }
+
+    /// Whether the version, which may have architecture as build metadata, applies to this release
+    pub fn matches(&self, version: &Version) -> bool {
+        ArchVersion::parse(version)
+            .map(|v| v.matches(&self.version, self.arch))
+            .unwrap_or(false)
+    }
+
+    fn metadata_versions(&self, annotation: Annotation) -> Vec<Version> {
+        match &self.release {
+            Release::Concrete(c) => c
+                .metadata
+                .get(annotation.as_str())
+                .and_then(|value| raw_metadata::parse_versions(value).ok())
+                .unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Update edge between two releases
+#[derive(Clone, Debug)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    /// Blocked edge files which remove this edge
+    pub blocked_by: Vec<PathBuf>,
+}
+
+impl Edge {
+    pub fn is_blocked(&self) -> bool {
+        !self.blocked_by.is_empty()
+    }
+}
+
+/// Upgrade graph as Cincinnati would serve it. Blocked edges are kept, but marked
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Build the graph from scraped releases, applying edge overrides and blocked edges
+    pub fn build(releases: &[ScrapedRelease], data: &GraphData) -> Fallible<Self> {
+        let mut nodes: Vec<Node> = releases.iter().map(Node::new).collect();
+        nodes.sort_by(|a, b| (&a.version, &a.arch).cmp(&(&b.version, &b.arch)));
+        let scraped: HashMap<&str, &ScrapedRelease> =
+            releases.iter().map(|r| (r.release.version(), r)).collect();
+        let index: HashMap<(String, Arch), usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| ((n.version.to_string(), n.arch), i))
+            .collect();
+        let find = |version: &Version, arch: Arch| -> Option<usize> {
+            index.get(&(version.to_string(), arch)).cloned()
+        };
+
+        let mut edges: BTreeSet<(usize, usize)> = BTreeSet::new();
+        let mut removed: Vec<Vec<Version>> = vec![];
+        for (i, node) in nodes.iter().enumerate() {
+            let release = scraped[node.release.version()];
+            let mut previous: Vec<Version> = release.previous.clone();
+            previous.extend(node.metadata_versions(Annotation::PreviousAdd));
+            let mut node_removed: Vec<Version> = node.metadata_versions(Annotation::PreviousRemove);
+            if let Some(overrides) = data.overrides.get(&node.version) {
+                previous.extend(overrides.previous_add.iter().cloned());
+                node_removed.extend(overrides.previous_remove.iter().cloned());
+            }
+            removed.push(node_removed);
+
+            for v in previous.iter() {
+                if let Some(from) = find(v, node.arch) {
+                    edges.insert((from, i));
+                }
+            }
+            for v in release.next.iter() {
+                if let Some(to) = find(v, node.arch) {
+                    edges.insert((i, to));
+                }
+            }
+        }
+
+        let blocks: Vec<(&BlockedEdgeFile, regex::Regex)> = data
+            .blocked_edges
+            .iter()
+            .map(|b| Ok((b, blocked_edges::anchored_regex(b.edge.from.as_str())?)))
+            .collect::<Fallible<_>>()?;
+        let edges = edges
+            .into_iter()
+            .filter(|(from, to)| !removed[*to].contains(&nodes[*from].version))
+            .map(|(from, to)| Edge {
+                from,
+                to,
+                blocked_by: blocks
+                    .iter()
+                    .filter(|(b, regex)| {
+                        nodes[to].matches(&b.edge.to)
+                            && regex.is_match(&nodes[from].version.to_string())
+                    })
+                    .map(|(b, _)| b.path.clone())
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Graph { nodes, edges })
+    }
+
+    /// Subgraph of releases listed in the channel for the given architecture
+    pub fn filter(&self, channel: &Channel, arch: Arch) -> Graph {
+        self.subgraph(|n| n.arch == arch && channel.versions.iter().any(|v| n.matches(v)))
+    }
+
+    /// Subgraphs of each channel for every architecture in the graph, keyed by channel name and architecture
+    pub fn channel_graphs(&self, channels: &[Channel]) -> BTreeMap<(String, Arch), Graph> {
+        let arches: BTreeSet<Arch> = self.nodes.iter().map(|n| n.arch).collect();
+        let mut result = BTreeMap::new();
+        for channel in channels.iter() {
+            for arch in arches.iter() {
+                result.insert((channel.name.clone(), *arch), self.filter(channel, *arch));
+            }
+        }
+        result
+    }
+
+    /// Subgraph of releases for the given architecture
+    pub fn filter_arch(&self, arch: Arch) -> Graph {
+        self.subgraph(|n| n.arch == arch)
+    }
+
+    fn subgraph<F: Fn(&Node) -> bool>(&self, keep: F) -> Graph {
+        let mut index: HashMap<usize, usize> = HashMap::new();
+        let mut nodes: Vec<Node> = vec![];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if keep(node) {
+                index.insert(i, nodes.len());
+                nodes.push(node.clone());
+            }
+        }
+        let edges = self
+            .edges
+            .iter()
+            .filter_map(|e| {
+                Some(Edge {
+                    from: *index.get(&e.from)?,
+                    to: *index.get(&e.to)?,
+                    blocked_by: e.blocked_by.clone(),
+                })
+            })
+            .collect();
+        Graph { nodes, edges }
+    }
+
+    /// Index of the node matching the version
+    pub fn find(&self, version: &Version) -> Option<usize> {
+        self.nodes.iter().position(|n| n.matches(version))
+    }
+
+    /// Edges which are not blocked
+    pub fn served_edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(|e| !e.is_blocked())
+    }
+
+    /// Print nodes and edges
+    pub fn print(&self) {
+        println!(
+            "{} nodes, {} edges, {} blocked",
+            self.nodes.len(),
+            self.served_edges().count(),
+            self.edges.iter().filter(|e| e.is_blocked()).count()
+        );
+        for node in self.nodes.iter() {
+            println!("{}", node.release.version());
+        }
+        for edge in self.edges.iter() {
+            let from = self.nodes[edge.from].release.version();
+            let to = self.nodes[edge.to].release.version();
+            if edge.is_blocked() {
+                println!("{} -> {} blocked by {:?}", from, to, edge.blocked_by);
+            } else {
+                println!("{} -> {}", from, to);
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::arch::Arch;
+use crate::check_releases::ScrapedRelease;
+use crate::graph::{Graph, GraphData};
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// Graph data directory, either given or extracted from a git revision
+enum Side {
+    Dir(PathBuf),
+    Rev(TempDir),
+}
+
+impl Side {
+    /// Use `spec` as a directory if it exists, otherwise extract it as a git revision of the data directory
+    fn resolve(data_dir: &Path, spec: &str) -> Fallible<Self> {
+        let path = PathBuf::from(spec);
+        if path.is_dir() {
+            return Ok(Side::Dir(path));
+        }
+
+        let dir = TempDir::new().context("Creating temporary directory")?;
+        let mut archive = Command::new("git")
+            .arg("-C")
+            .arg(data_dir)
+            .arg("archive")
+            .arg("--format=tar")
+            .arg(format!("{}:./", spec))
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("Running git archive")?;
+        let stdout = archive.stdout.take().expect("stdout is piped");
+        let extracted = Command::new("tar")
+            .arg("-x")
+            .arg("-C")
+            .arg(dir.path())
+            .stdin(stdout)
+            .status()
+            .context("Running tar")?;
+        let archived = archive.wait().context("Running git archive")?;
+        if !archived.success() || !extracted.success() {
+            return Err(anyhow::anyhow!(
+                "{:?} is neither a directory nor a git revision of {:?}",
+                spec,
+                data_dir
+            ));
+        }
+        Ok(Side::Rev(dir))
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Side::Dir(path) => path,
+            Side::Rev(dir) => dir.path(),
+        }
+    }
+}
+
+/// Served nodes and edges of a graph, by release name
+struct Served {
+    nodes: BTreeSet<String>,
+    edges: BTreeSet<(String, String)>,
+    /// Blocked edge files of edges which are not served, relative to the data directory
+    blocked: BTreeMap<(String, String), Vec<PathBuf>>,
+}
+
+impl Served {
+    fn new(graph: &Graph, root: &Path) -> Self {
+        let name = |i: usize| graph.nodes[i].release.version().to_string();
+        let mut served = Served {
+            nodes: (0..graph.nodes.len()).map(name).collect(),
+            edges: BTreeSet::new(),
+            blocked: BTreeMap::new(),
+        };
+        for edge in graph.edges.iter() {
+            let key = (name(edge.from), name(edge.to));
+            if edge.is_blocked() {
+                let files = edge
+                    .blocked_by
+                    .iter()
+                    .map(|p| p.strip_prefix(root).unwrap_or(p).to_path_buf())
+                    .collect();
+                served.blocked.insert(key, files);
+            } else {
+                served.edges.insert(key);
+            }
+        }
+        served
+    }
+}
+
+/// Changes between base and head for a channel and architecture
+fn diff(base: &Served, head: &Served) -> Vec<String> {
+    let mut changes: Vec<String> = vec![];
+    for node in head.nodes.difference(&base.nodes) {
+        changes.push(format!("+ node {}", node));
+    }
+    for node in base.nodes.difference(&head.nodes) {
+        changes.push(format!("- node {}", node));
+    }
+    for (from, to) in head.edges.difference(&base.edges) {
+        changes.push(format!("+ edge {} -> {}", from, to));
+    }
+    for key in base.edges.difference(&head.edges) {
+        let (from, to) = key;
+        match head.blocked.get(key) {
+            Some(files) => {
+                changes.push(format!("- edge {} -> {} blocked by {:?}", from, to, files))
+            }
+            None => changes.push(format!("- edge {} -> {}", from, to)),
+        }
+    }
+    changes
+}
+
+/// Print nodes and edges which were added or removed in served graphs of every channel and architecture
+pub fn run(data_dir: &Path, releases: &[ScrapedRelease], base: &str, head: &str) -> Fallible<()> {
+    let base_side = Side::resolve(data_dir, base)?;
+    let head_side = Side::resolve(data_dir, head)?;
+    let base_data = GraphData::read(base_side.path()).context(format!("Reading {}", base))?;
+    let head_data = GraphData::read(head_side.path()).context(format!("Reading {}", head))?;
+    let base_graph = Graph::build(releases, &base_data)?;
+    let head_graph = Graph::build(releases, &head_data)?;
+
+    let base_graphs = base_graph.channel_graphs(&base_data.channels);
+    let head_graphs = head_graph.channel_graphs(&head_data.channels);
+    let keys: BTreeSet<&(String, Arch)> = base_graphs.keys().chain(head_graphs.keys()).collect();
+    let empty = Graph {
+        nodes: vec![],
+        edges: vec![],
+    };
+
+    let mut changed = false;
+    for key in keys {
+        let changes = diff(
+            &Served::new(base_graphs.get(key).unwrap_or(&empty), base_side.path()),
+            &Served::new(head_graphs.get(key).unwrap_or(&empty), head_side.path()),
+        );
+        if changes.is_empty() {
+            continue;
+        }
+        changed = true;
+        println!("{} ({}):", key.0, key.1);
+        for change in changes {
+            println!("  {}", change);
+        }
+    }
+    if !changed {
+        println!("No changes in served graphs");
+    }
+    Ok(())
+}
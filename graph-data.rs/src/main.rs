@@ -1,20 +1,465 @@
+mod arch;
+mod backfill;
+mod blocked_edge_impact;
+mod blocked_edges;
+mod build_suggestions;
+mod channels;
 mod check_releases;
 mod check_signatures;
+mod fmt;
 mod gpg;
+mod graph;
+mod graph_diff;
+mod raw_metadata;
+mod registry_config;
+mod registry_fixtures;
+mod render;
+mod schema_version;
+mod scrape_cache;
+mod stranded;
+mod upgrade_path;
+mod verification_config;
 mod verify_yaml;
 
+use crate::arch::Arch;
+use crate::verification_config::VerificationConfig;
+
+use anyhow::Context;
 use anyhow::Result as Fallible;
+use semver::Version;
+use std::path::PathBuf;
+use structopt::StructOpt;
 
+use cincinnati::plugins::internal::release_scrape_dockerv2::plugin::ReleaseScrapeDockerv2Settings;
 use cincinnati::Release;
 
-async fn run_all_tests() -> Fallible<()> {
-    let found_versions = verify_yaml::run().await?;
-    let releases: Vec<Release> = check_releases::run(&found_versions).await?;
-    check_signatures::run(&releases, &found_versions).await?;
-    Ok(())
+/// Verify Cincinnati graph data
+#[derive(Debug, StructOpt)]
+#[structopt(name = "cincinnati-graph-data")]
+struct Options {
+    /// Root directory of the graph data
+    #[structopt(
+        long = "data-dir",
+        env = "GRAPH_DATA_DIR",
+        default_value = ".",
+        parse(from_os_str)
+    )]
+    data_dir: PathBuf,
+
+    /// TOML file with registry settings, which are overridden by command line options
+    #[structopt(
+        long = "registry-config",
+        env = "GRAPH_DATA_REGISTRY_CONFIG",
+        parse(from_os_str)
+    )]
+    registry_config: Option<PathBuf>,
+
+    /// Registry to scrape release images from
+    #[structopt(long = "registry", env = "GRAPH_DATA_REGISTRY")]
+    registry: Option<String>,
+
+    /// Repository in the registry which contains release images
+    #[structopt(long = "repository", env = "GRAPH_DATA_REPOSITORY")]
+    repository: Option<String>,
+
+    /// Username for the registry
+    #[structopt(long = "username", env = "GRAPH_DATA_REGISTRY_USERNAME")]
+    username: Option<String>,
+
+    /// Password for the registry
+    #[structopt(
+        long = "password",
+        env = "GRAPH_DATA_REGISTRY_PASSWORD",
+        hide_env_values = true
+    )]
+    password: Option<String>,
+
+    /// Docker config.json style file with registry credentials
+    #[structopt(
+        long = "auth-file",
+        env = "GRAPH_DATA_REGISTRY_AUTH_FILE",
+        parse(from_os_str)
+    )]
+    auth_file: Option<PathBuf>,
+
+    /// Number of release images fetched concurrently
+    #[structopt(long = "fetch-concurrency", env = "GRAPH_DATA_FETCH_CONCURRENCY")]
+    fetch_concurrency: Option<usize>,
+
+    /// Directory where scraped release metadata is cached between runs
+    #[structopt(
+        long = "scrape-cache",
+        env = "GRAPH_DATA_SCRAPE_CACHE",
+        parse(from_os_str)
+    )]
+    scrape_cache: Option<PathBuf>,
+
+    /// Fetch all release metadata from the registry, ignoring the scrape cache
+    #[structopt(long = "no-scrape-cache")]
+    no_scrape_cache: bool,
+
+    /// Read release metadata from a fixture directory instead of scraping the registry
+    #[structopt(
+        long = "registry-fixtures",
+        env = "GRAPH_DATA_REGISTRY_FIXTURES",
+        parse(from_os_str)
+    )]
+    registry_fixtures: Option<PathBuf>,
+
+    /// Record scraped release metadata to a fixture directory
+    #[structopt(
+        long = "record-fixtures",
+        conflicts_with = "registry-fixtures",
+        parse(from_os_str)
+    )]
+    record_fixtures: Option<PathBuf>,
+
+    /// Signature store: HTTP(S) base URL, file:// URL or a local directory.
+    /// Can be repeated, stores are tried in order [default: https://mirror.openshift.com/pub/openshift-v4/signatures/openshift/release/]
+    #[structopt(long = "signature-store", number_of_values = 1)]
+    signature_store: Vec<check_signatures::SignatureStore>,
+
+    /// Armored public key file, possibly with several keys, or a directory of them.
+    /// Can be repeated [default: /usr/local/share/public-keys/]
+    #[structopt(long = "keyring", number_of_values = 1, parse(from_os_str))]
+    keyring: Vec<PathBuf>,
+
+    /// Release verification ConfigMap manifest from cluster-update-keys. Its stores and
+    /// public keys are used instead of --signature-store and --keyring, which cannot be given with it
+    #[structopt(
+        long = "verification-config",
+        env = "GRAPH_DATA_VERIFICATION_CONFIG",
+        conflicts_with_all = &["signature-store", "keyring"],
+        parse(from_os_str)
+    )]
+    verification_config: Option<PathBuf>,
+
+    /// Channels which must have payloads for all architectures, comma separated
+    #[structopt(long = "multi-arch", use_delimiter = true)]
+    multi_arch: Vec<String>,
+
+    /// Write the release coverage report as JSON to this file
+    #[structopt(long = "coverage-json", parse(from_os_str))]
+    coverage_json: Option<PathBuf>,
+
+    /// Only run checks which don't need network access. Releases are read from
+    /// --registry-fixtures and signatures from local signature stores, if given
+    #[structopt(long = "offline")]
+    offline: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Run all checks (default)
+    All,
+    /// Verify that channel and blocked edge files are valid
+    VerifyYaml,
+    /// Verify that all releases mentioned in graph data are published
+    CheckReleases,
+    /// Verify that all releases mentioned in graph data are signed
+    CheckSignatures,
+    /// Verify that no release in a channel is left without an update path
+    CheckStranded,
+    /// Remove scrape cache entries which were not used recently
+    PruneCache {
+        /// Remove entries not used for this many days
+        #[structopt(long = "max-age-days", default_value = "30")]
+        max_age_days: u64,
+    },
+    /// Rewrite channel files so that versions are listed in canonical order
+    Fmt {
+        /// Don't write files, fail if any of them is not formatted
+        #[structopt(long = "check")]
+        check: bool,
+    },
+    /// Inspect the upgrade graph which Cincinnati would serve
+    Graph(GraphCommand),
+    /// Find update paths between two releases in a channel
+    Path {
+        /// Version to update from
+        #[structopt(long = "from")]
+        from: Version,
+        /// Version to update to
+        #[structopt(long = "to")]
+        to: Version,
+        /// Channel name, i.e. stable-4.6
+        #[structopt(long = "channel")]
+        channel: String,
+        /// Release architecture
+        #[structopt(long = "arch", default_value = "amd64")]
+        arch: Arch,
+    },
+    /// Show how served graphs differ between two directories or git revisions of graph data
+    Diff {
+        /// Base directory or git revision
+        #[structopt(long = "base")]
+        base: String,
+        /// Head directory or git revision
+        #[structopt(long = "head")]
+        head: String,
+    },
+    /// Copy versions between channels of adjacent minor versions, so that both list them
+    Backfill {
+        /// Print changes without writing them
+        #[structopt(long = "dry-run")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum GraphCommand {
+    /// Print releases and edges of a channel
+    Show {
+        /// Channel name, i.e. stable-4.6
+        #[structopt(long = "channel")]
+        channel: String,
+        /// Release architecture
+        #[structopt(long = "arch", default_value = "amd64")]
+        arch: Arch,
+    },
+    /// Render a channel graph for review
+    Render {
+        /// Channel name, i.e. stable-4.6
+        #[structopt(long = "channel")]
+        channel: String,
+        /// Release architecture
+        #[structopt(long = "arch", default_value = "amd64")]
+        arch: Arch,
+        /// Output format: dot or mermaid
+        #[structopt(long = "format", default_value = "dot")]
+        format: render::Format,
+        /// File to write the rendered graph to, stdout if not set
+        #[structopt(long = "output", short = "o", parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Report edges removed by each blocked edge file
+    BlockedEdges,
+}
+
+impl Options {
+    /// Scrape cache directory, unless the cache is disabled
+    fn scrape_cache_dir(&self) -> Option<PathBuf> {
+        if self.no_scrape_cache {
+            return None;
+        }
+        self.scrape_cache.clone().or_else(scrape_cache::default_dir)
+    }
+
+    /// Signature stores and public keys to verify release signatures with.
+    /// None in offline mode if any signature store needs network access
+    fn verification_config(&self) -> Fallible<Option<VerificationConfig>> {
+        let remote = |stores: &[check_signatures::SignatureStore]| {
+            self.offline && stores.iter().any(|s| !s.is_local())
+        };
+        if let Some(path) = &self.verification_config {
+            let config = VerificationConfig::read(path)?;
+            return Ok(if remote(&config.stores) {
+                None
+            } else {
+                Some(config)
+            });
+        }
+
+        let stores = if self.signature_store.is_empty() {
+            vec![check_signatures::DEFAULT_STORE.parse()?]
+        } else {
+            self.signature_store.clone()
+        };
+        if remote(&stores) {
+            return Ok(None);
+        }
+        let keyring = if self.keyring.is_empty() {
+            vec![PathBuf::from(gpg::DEFAULT_KEYRING)]
+        } else {
+            self.keyring.clone()
+        };
+        Ok(Some(VerificationConfig {
+            stores,
+            keys: gpg::load_public_keys(&keyring)?,
+        }))
+    }
+
+    /// Build scrape settings, overriding defaults with the values from the config file
+    /// and then from the command line
+    fn scrape_settings(&self) -> Fallible<ReleaseScrapeDockerv2Settings> {
+        let config = match &self.registry_config {
+            Some(path) => registry_config::RegistryConfig::read(path)?,
+            None => registry_config::RegistryConfig::default(),
+        };
+        let mut settings = ReleaseScrapeDockerv2Settings::default();
+        if let Some(registry) = self.registry.as_ref().or(config.registry.as_ref()) {
+            settings.registry = registry.clone();
+        }
+        if let Some(repository) = self.repository.as_ref().or(config.repository.as_ref()) {
+            settings.repository = repository.clone();
+        }
+        if let Some(concurrency) = self.fetch_concurrency.or(config.fetch_concurrency) {
+            settings.fetch_concurrency = concurrency;
+        }
+
+        // Credentials from the command line replace the config file ones as a whole,
+        // so an auth file on the command line wins over a username and password in the config
+        let cli = registry_config::RegistryConfig {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            auth_file: self.auth_file.clone(),
+            ..Default::default()
+        };
+        let credentials = match cli
+            .credentials(&settings.registry)
+            .context("Command line registry credentials")?
+        {
+            Some(credentials) => Some(credentials),
+            None => config
+                .credentials(&settings.registry)
+                .context("Registry config credentials")?,
+        };
+        if let Some((username, password)) = credentials {
+            settings.username = Some(username);
+            settings.password = Some(password);
+        }
+        Ok(settings)
+    }
+}
+
+/// Names of the checks which need network access
+static REMOTE_CHECKS: &[&str] = &["check-releases", "check-signatures"];
+
+async fn run(options: Options) -> Fallible<()> {
+    let command = options.command.as_ref().unwrap_or(&Command::All);
+    match command {
+        Command::Fmt { check } => return fmt::run(&options.data_dir, *check),
+        Command::Backfill { dry_run } => return backfill::run(&options.data_dir, *dry_run),
+        Command::PruneCache { max_age_days } => {
+            return match options.scrape_cache_dir() {
+                Some(dir) => scrape_cache::prune(&dir, *max_age_days),
+                None => Err(anyhow::anyhow!("Scrape cache is disabled")),
+            };
+        }
+        _ => {}
+    }
+    // Without fixtures releases can only be scraped from the registry
+    let scrape_offline = options.offline && options.registry_fixtures.is_none();
+    if scrape_offline {
+        if let Command::CheckReleases
+        | Command::CheckSignatures
+        | Command::CheckStranded
+        | Command::Graph(_)
+        | Command::Path { .. }
+        | Command::Diff { .. } = command
+        {
+            return Err(anyhow::anyhow!(
+                "Scraping releases needs network access, use --registry-fixtures in offline mode"
+            ));
+        }
+    }
+
+    let found_versions = verify_yaml::run(&options.data_dir).await?;
+    if scrape_offline {
+        if let Command::All = command {
+            println!(
+                "Offline mode: remote checks were SKIPPED, not passed: {}",
+                REMOTE_CHECKS.join(", ")
+            );
+        }
+        return Ok(());
+    }
+
+    if let Command::VerifyYaml = command {
+        return Ok(());
+    }
+
+    let fetched = match &options.registry_fixtures {
+        Some(dir) => registry_fixtures::read(dir)?,
+        None => {
+            check_releases::scrape(
+                &options.scrape_settings()?,
+                options.scrape_cache_dir().as_deref(),
+            )
+            .await?
+        }
+    };
+    if let Some(dir) = &options.record_fixtures {
+        registry_fixtures::record(dir, &fetched)?;
+    }
+    let scraped = check_releases::run(fetched, &found_versions)?;
+    let releases: Vec<Release> = scraped.iter().map(|s| s.release.clone()).collect();
+    raw_metadata::verify_references(&options.data_dir, &found_versions, &releases)?;
+    if let Command::All | Command::CheckReleases = command {
+        let data = graph::GraphData::read(&options.data_dir)?;
+        check_releases::report_coverage(
+            &data.channels,
+            &scraped,
+            &options.multi_arch,
+            options.coverage_json.as_deref(),
+        )?;
+    }
+
+    match command {
+        Command::All | Command::CheckSignatures => match options.verification_config()? {
+            Some(config) => check_signatures::run(&releases, &found_versions, &config).await,
+            None if matches!(command, Command::CheckSignatures) => Err(anyhow::anyhow!(
+                "Signature stores need network access, use a local --signature-store in offline mode"
+            )),
+            None => {
+                println!("Offline mode: check-signatures was SKIPPED, not passed");
+                Ok(())
+            }
+        },
+        Command::Graph(graph_command) => {
+            let data = graph::GraphData::read(&options.data_dir)?;
+            let graph = graph::Graph::build(&scraped, &data)?;
+            match graph_command {
+                GraphCommand::Show { channel, arch } => {
+                    graph.filter(data.channel(channel)?, *arch).print()
+                }
+                GraphCommand::Render {
+                    channel,
+                    arch,
+                    format,
+                    output,
+                } => {
+                    let filtered = graph.filter(data.channel(channel)?, *arch);
+                    let title = format!("{} {}", channel, arch);
+                    let rendered = render::render(&filtered, &title, format);
+                    match output {
+                        Some(output) => {
+                            std::fs::write(output, rendered)
+                                .context(format!("Writing {:?}", output))?;
+                            println!("Rendered {} to {:?}", title, output);
+                        }
+                        None => print!("{}", rendered),
+                    }
+                }
+                GraphCommand::BlockedEdges => blocked_edge_impact::run(&graph, &data),
+            }
+            Ok(())
+        }
+        Command::Path {
+            from,
+            to,
+            channel,
+            arch,
+        } => {
+            let data = graph::GraphData::read(&options.data_dir)?;
+            let graph = graph::Graph::build(&scraped, &data)?;
+            upgrade_path::run(&graph, &data, channel, *arch, from, to)
+        }
+        Command::CheckStranded => {
+            let data = graph::GraphData::read(&options.data_dir)?;
+            let graph = graph::Graph::build(&scraped, &data)?;
+            stranded::run(&graph, &data)
+        }
+        Command::Diff { base, head } => graph_diff::run(&options.data_dir, &scraped, base, head),
+        _ => Ok(()),
+    }
 }
 
 fn main() -> Fallible<()> {
+    let options = Options::from_args();
     let mut runtime = tokio::runtime::Runtime::new()?;
-    runtime.block_on(run_all_tests())
+    runtime.block_on(run(options))
 }
@@ -0,0 +1,140 @@
+use crate::arch::{self, ArchVersion};
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use cincinnati::Release;
+use semver::Version;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+pub static RAW_METADATA_FILE: &str = "raw/metadata.json";
+
+/// Annotations which can be overridden for a release
+#[derive(Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Annotation {
+    #[serde(rename = "io.openshift.upgrades.graph.previous.add")]
+    PreviousAdd,
+    #[serde(rename = "io.openshift.upgrades.graph.previous.remove")]
+    PreviousRemove,
+}
+
+impl Annotation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Annotation::PreviousAdd => "io.openshift.upgrades.graph.previous.add",
+            Annotation::PreviousRemove => "io.openshift.upgrades.graph.previous.remove",
+        }
+    }
+}
+
+/// Contents of the raw metadata file: release name to overridden annotations
+pub type RawMetadata = BTreeMap<String, BTreeMap<Annotation, String>>;
+
+/// Edges added or removed for a release
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub previous_add: Vec<Version>,
+    pub previous_remove: Vec<Version>,
+}
+
+/// Parse a comma-separated list of versions
+pub fn parse_versions(value: &str) -> Result<Vec<Version>, Vec<String>> {
+    let (versions, errors): (Vec<_>, Vec<_>) = value
+        .split(',')
+        .map(|v| Version::from_str(v.trim()).map_err(|e| format!("{:?}: {}", v, e)))
+        .partition(Result::is_ok);
+    if errors.is_empty() {
+        Ok(versions.into_iter().map(Result::unwrap).collect())
+    } else {
+        Err(errors.into_iter().map(Result::unwrap_err).collect())
+    }
+}
+
+/// Read raw metadata file and parse its versions
+pub fn read(data_dir: &Path) -> Fallible<BTreeMap<Version, Overrides>> {
+    let path = data_dir.join(RAW_METADATA_FILE);
+    let contents = read_to_string(&path).context(format!("Reading {:?}", path))?;
+    let raw: RawMetadata =
+        serde_json::from_str(&contents).context(format!("Parsing {:?}", path))?;
+
+    let mut errors: Vec<String> = vec![];
+    let mut result: BTreeMap<Version, Overrides> = BTreeMap::new();
+    for (name, annotations) in raw.iter() {
+        let version = match Version::from_str(name) {
+            Ok(v) => v,
+            Err(e) => {
+                errors.push(format!("{:?}: {}", name, e));
+                continue;
+            }
+        };
+        let overrides = result.entry(version).or_default();
+        for (annotation, value) in annotations.iter() {
+            let versions = match parse_versions(value) {
+                Ok(v) => v,
+                Err(e) => {
+                    errors.extend(
+                        e.into_iter()
+                            .map(|e| format!("{}: {}: {}", name, annotation.as_str(), e)),
+                    );
+                    continue;
+                }
+            };
+            match annotation {
+                Annotation::PreviousAdd => overrides.previous_add.extend(versions),
+                Annotation::PreviousRemove => overrides.previous_remove.extend(versions),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(result)
+    } else {
+        Err(anyhow::anyhow!("Invalid versions in {:?}: {:#?}", path, errors))
+    }
+}
+
+/// Verify that raw metadata file is valid
+pub fn run(data_dir: &Path) -> Fallible<()> {
+    println!("Verifying raw metadata overrides are valid");
+    read(data_dir)?;
+    Ok(())
+}
+
+/// Verify that all versions in raw metadata are either mentioned in channels or released
+pub fn verify_references(
+    data_dir: &Path,
+    found_versions: &HashSet<ArchVersion>,
+    releases: &[Release],
+) -> Fallible<()> {
+    println!("Verifying raw metadata overrides reference known releases");
+    let released_versions: HashSet<Version> = releases
+        .iter()
+        .filter_map(|r| arch::release_arch(r).ok())
+        .map(|(version, _)| version)
+        .chain(found_versions.iter().map(|v| v.version.clone()))
+        .collect();
+
+    let mut unknown: Vec<String> = vec![];
+    for (version, overrides) in read(data_dir)?.iter() {
+        let referenced = std::iter::once(version)
+            .chain(overrides.previous_add.iter())
+            .chain(overrides.previous_remove.iter());
+        for v in referenced {
+            if !released_versions.contains(v) {
+                unknown.push(format!("{}: {}", version, v));
+            }
+        }
+    }
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Raw metadata references unknown releases: {:#?}",
+            unknown
+        ))
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+/// Registry settings read from a TOML config file. Command line options take precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistryConfig {
+    pub registry: Option<String>,
+    pub repository: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Docker config.json style file with registry credentials
+    pub auth_file: Option<PathBuf>,
+    pub fetch_concurrency: Option<usize>,
+}
+
+impl RegistryConfig {
+    pub fn read(path: &Path) -> Fallible<Self> {
+        let contents = read_to_string(path).context(format!("Reading {:?}", path))?;
+        toml::from_str(&contents).context(format!("Parsing {:?}", path))
+    }
+
+    /// Username and password for the registry, either set directly or read from the auth file
+    pub fn credentials(&self, registry: &str) -> Fallible<Option<(String, String)>> {
+        match (&self.username, &self.password, &self.auth_file) {
+            (Some(_), Some(_), Some(_)) => Err(anyhow::anyhow!(
+                "Registry username and password cannot be set together with an auth file"
+            )),
+            (Some(username), Some(password), None) => {
+                Ok(Some((username.clone(), password.clone())))
+            }
+            (None, None, Some(path)) => read_auth_file(path, registry).map(Some),
+            (None, None, None) => Ok(None),
+            _ => Err(anyhow::anyhow!(
+                "Registry username and password must be set together"
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthFile {
+    #[serde(default)]
+    auths: HashMap<String, AuthEntry>,
+}
+
+#[derive(Deserialize)]
+struct AuthEntry {
+    /// Base64 encoded `username:password`
+    auth: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// Host part of an auth file key, i.e. `quay.io` for `https://quay.io/v1/`
+fn auth_host(key: &str) -> &str {
+    let key = match key.find("://") {
+        Some(i) => &key[i + 3..],
+        None => key,
+    };
+    key.split('/').next().unwrap_or(key)
+}
+
+/// Read username and password for the registry from a docker config.json style auth file
+pub fn read_auth_file(path: &Path, registry: &str) -> Fallible<(String, String)> {
+    let contents = read_to_string(path).context(format!("Reading {:?}", path))?;
+    let file: AuthFile = serde_json::from_str(&contents).context(format!("Parsing {:?}", path))?;
+    let host = auth_host(registry);
+    let entry = file
+        .auths
+        .iter()
+        .find(|(key, _)| auth_host(key) == host)
+        .map(|(_, entry)| entry)
+        .ok_or_else(|| anyhow::anyhow!("No credentials for {} in {:?}", registry, path))?;
+
+    if let (Some(username), Some(password)) = (&entry.username, &entry.password) {
+        return Ok((username.clone(), password.clone()));
+    }
+    let auth = entry.auth.as_ref().ok_or_else(|| {
+        anyhow::anyhow!("Credentials for {} in {:?} have no auth", registry, path)
+    })?;
+    let decoded = base64::decode(auth.trim())
+        .context(format!("Decoding auth for {} in {:?}", registry, path))?;
+    let decoded = String::from_utf8(decoded)
+        .context(format!("Decoding auth for {} in {:?}", registry, path))?;
+    match decoded.find(':') {
+        Some(i) => Ok((decoded[..i].to_string(), decoded[i + 1..].to_string())),
+        None => Err(anyhow::anyhow!(
+            "Auth for {} in {:?} is not in username:password form",
+            registry,
+            path
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn auth_file(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn auth_host_strips_scheme_and_path() {
+        assert_eq!(auth_host("https://quay.io/v1/"), "quay.io");
+        assert_eq!(auth_host("quay.io"), "quay.io");
+    }
+
+    #[test]
+    fn base64_auth_is_decoded_for_matching_host() {
+        // "user:pa:ss"
+        let file = auth_file(r#"{"auths": {"https://quay.io/v1/": {"auth": "dXNlcjpwYTpzcw=="}}}"#);
+        assert_eq!(
+            read_auth_file(file.path(), "quay.io").unwrap(),
+            ("user".to_string(), "pa:ss".to_string())
+        );
+        assert!(read_auth_file(file.path(), "registry.example.com").is_err());
+    }
+
+    #[test]
+    fn credentials_and_auth_file_are_exclusive() {
+        let file = auth_file(r#"{"auths": {"quay.io": {"username": "a", "password": "b"}}}"#);
+        let config = RegistryConfig {
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+            auth_file: Some(file.path().to_path_buf()),
+            ..Default::default()
+        };
+        assert!(config.credentials("quay.io").is_err());
+
+        let config = RegistryConfig {
+            username: Some("user".to_string()),
+            ..Default::default()
+        };
+        assert!(config.credentials("quay.io").is_err());
+
+        let config = RegistryConfig {
+            auth_file: Some(file.path().to_path_buf()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.credentials("quay.io").unwrap(),
+            Some(("a".to_string(), "b".to_string()))
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use cincinnati::plugins::internal::release_scrape_dockerv2::registry::Release;
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use std::ffi::OsStr;
+use std::fs::{create_dir_all, read_dir, read_to_string, write};
+use std::path::Path;
+
+/// Read releases from a fixture directory instead of scraping a registry.
+/// Each `<version>.json` file holds the image pullspec with its manifest digest
+/// and the release-metadata of the image, as written by `record`
+pub fn read(dir: &Path) -> Fallible<Vec<Release>> {
+    println!("Reading release fixtures from {:?}", dir);
+    let mut paths: Vec<_> = read_dir(dir)
+        .context(format!("Reading {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|p| p.extension() == Some(OsStr::new("json")));
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let contents = read_to_string(path).context(format!("Reading {:?}", path))?;
+            serde_json::from_str(&contents).context(format!("Parsing {:?}", path))
+        })
+        .collect()
+}
+
+/// Write scraped releases to a fixture directory, which can be passed to `read` later
+pub fn record(dir: &Path, releases: &[Release]) -> Fallible<()> {
+    create_dir_all(dir).context(format!("Creating {:?}", dir))?;
+    for release in releases {
+        let path = dir.join(format!("{}.json", release.metadata.version));
+        let contents = serde_json::to_string_pretty(release)?;
+        write(&path, format!("{}\n", contents)).context(format!("Writing {:?}", path))?;
+    }
+    println!("Recorded {} release fixtures to {:?}", releases.len(), dir);
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::graph::{Graph, Node};
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Output format of a rendered graph
+#[derive(Debug)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "mermaid" => Ok(Format::Mermaid),
+            _ => Err(anyhow::anyhow!(
+                "Unknown format {:?}, expected dot or mermaid",
+                s
+            )),
+        }
+    }
+}
+
+// Colours of served and blocked edges
+static EDGE_COLOR: &str = "black";
+static BLOCKED_EDGE_COLOR: &str = "red";
+
+/// Node indexes grouped by `major.minor`
+fn clusters(graph: &Graph) -> BTreeMap<(u64, u64), Vec<usize>> {
+    let mut clusters: BTreeMap<(u64, u64), Vec<usize>> = BTreeMap::new();
+    for (i, node) in graph.nodes.iter().enumerate() {
+        clusters
+            .entry((node.version.major, node.version.minor))
+            .or_default()
+            .push(i);
+    }
+    clusters
+}
+
+fn label(node: &Node) -> &str {
+    node.release.version()
+}
+
+fn render_dot(graph: &Graph, title: &str) -> String {
+    let mut lines: Vec<String> = vec![
+        format!("digraph \"{}\" {{", title),
+        "  rankdir=LR;".to_string(),
+    ];
+    for ((major, minor), nodes) in clusters(graph) {
+        lines.push(format!("  subgraph \"cluster_{}.{}\" {{", major, minor));
+        lines.push(format!("    label=\"{}.{}\";", major, minor));
+        for i in nodes {
+            lines.push(format!(
+                "    n{} [label=\"{}\"];",
+                i,
+                label(&graph.nodes[i])
+            ));
+        }
+        lines.push("  }".to_string());
+    }
+    for edge in graph.edges.iter() {
+        if edge.is_blocked() {
+            let blocked_by: Vec<String> = edge
+                .blocked_by
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            lines.push(format!(
+                "  n{} -> n{} [color={}, style=dashed, tooltip=\"blocked by {}\"];",
+                edge.from,
+                edge.to,
+                BLOCKED_EDGE_COLOR,
+                blocked_by.join(", ")
+            ));
+        } else {
+            lines.push(format!(
+                "  n{} -> n{} [color={}];",
+                edge.from, edge.to, EDGE_COLOR
+            ));
+        }
+    }
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+fn render_mermaid(graph: &Graph, title: &str) -> String {
+    let mut lines: Vec<String> = vec![format!("%% {}", title), "graph LR".to_string()];
+    for ((major, minor), nodes) in clusters(graph) {
+        lines.push(format!(
+            "  subgraph minor_{}_{} [\"{}.{}\"]",
+            major, minor, major, minor
+        ));
+        for i in nodes {
+            lines.push(format!("    n{}[\"{}\"]", i, label(&graph.nodes[i])));
+        }
+        lines.push("  end".to_string());
+    }
+    for edge in graph.edges.iter() {
+        let arrow = if edge.is_blocked() { "-.->" } else { "-->" };
+        lines.push(format!("  n{} {} n{}", edge.from, arrow, edge.to));
+    }
+    // Links are styled by their position in the list of edges
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let color = if edge.is_blocked() {
+            BLOCKED_EDGE_COLOR
+        } else {
+            EDGE_COLOR
+        };
+        lines.push(format!("  linkStyle {} stroke:{}", i, color));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Render the graph, blocked edges are drawn in a different colour and nodes are clustered by minor version
+pub fn render(graph: &Graph, title: &str, format: &Format) -> String {
+    match format {
+        Format::Dot => render_dot(graph, title),
+        Format::Mermaid => render_mermaid(graph, title),
+    }
+}
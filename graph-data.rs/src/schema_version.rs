@@ -0,0 +1,54 @@
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use semver::Version;
+use std::fs::read_to_string;
+use std::path::Path;
+
+// Name of the file which holds schema version
+static VERSION_FILE: &str = "version";
+
+// Highest schema version this tool understands
+static SUPPORTED_MAJOR: u64 = 1;
+static SUPPORTED_MINOR: u64 = 0;
+
+// Schema features verified by this tool
+static CHECKED_FEATURES: &[&str] = &[
+    "channels",
+    "blocked-edges",
+    "build-suggestions",
+    "raw/metadata.json",
+];
+
+/// Parse schema version file and verify it is compatible with the supported schema.
+/// See "Schema version" in README.md for compatibility rules
+pub fn run(data_dir: &Path) -> Fallible<Version> {
+    let path = data_dir.join(VERSION_FILE);
+    let contents = read_to_string(&path).context(format!("Reading {:?}", path))?;
+    let version = Version::parse(contents.trim())
+        .context(format!("Parsing {:?} as semantic version", path))?;
+    println!("Found schema version {}", version);
+
+    if version.major != SUPPORTED_MAJOR {
+        return Err(anyhow::anyhow!(
+            "Schema version {} is not supported, expected major version {}",
+            version,
+            SUPPORTED_MAJOR
+        ));
+    }
+    if version.minor > SUPPORTED_MINOR {
+        println!(
+            "Warning: schema version {} is newer than supported {}.{}.0. \
+             Features introduced in schema versions {}.{} through {}.{} are not checked, \
+             only {} are verified",
+            version,
+            SUPPORTED_MAJOR,
+            SUPPORTED_MINOR,
+            SUPPORTED_MAJOR,
+            SUPPORTED_MINOR + 1,
+            version.major,
+            version.minor,
+            CHECKED_FEATURES.join(", ")
+        );
+    }
+    Ok(version)
+}
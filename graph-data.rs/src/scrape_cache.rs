@@ -0,0 +1,166 @@
+use cincinnati::plugins::internal::release_scrape_dockerv2::registry;
+use cincinnati::plugins::internal::release_scrape_dockerv2::registry::cache::Cache;
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_file, write};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Default cache location, `$XDG_CACHE_HOME/cincinnati-graph-data` or `~/.cache/cincinnati-graph-data`
+pub fn default_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("cincinnati-graph-data"))
+}
+
+/// Key the scraper looks a release up by, a `DefaultHasher` hash of the release source
+/// `<registry>/<repository>@<manifest digest>`. The hash is only stable within a build,
+/// so it is recomputed when entries are loaded instead of being stored
+pub fn cache_key(release: &registry::Release) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    release.source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Manifest digest of the release image, i.e. `sha256:...` for `quay.io/repo@sha256:...`
+fn manifest_digest(release: &registry::Release) -> Option<&str> {
+    let i = release.source.rfind('@')?;
+    Some(&release.source[i + 1..])
+}
+
+/// Path of the cache entry. Entries are named after the manifest digest of the release image,
+/// which is immutable, so entries never go stale
+fn entry_path(dir: &Path, digest: &str) -> PathBuf {
+    dir.join(format!("{}.json", digest.replace(':', "-")))
+}
+
+fn entry_paths(dir: &Path) -> Fallible<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut paths: Vec<PathBuf> = read_dir(dir)
+        .context(format!("Reading {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|p| p.extension() == Some(OsStr::new("json")));
+    paths.sort();
+    Ok(paths)
+}
+
+/// Build a scrape cache filled with entries stored in the directory.
+/// Entries which cannot be read are skipped, they will be fetched again
+pub async fn load(dir: &Path) -> Fallible<Cache> {
+    let cache = registry::cache::new();
+    let mut loaded = 0;
+    {
+        let mut entries = cache.write().await;
+        for path in entry_paths(dir)? {
+            let release = read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| Ok(serde_json::from_str::<registry::Release>(&contents)?));
+            match release {
+                Ok(release) => {
+                    entries.insert(cache_key(&release), Some(release));
+                    loaded += 1;
+                }
+                Err(e) => println!("Warning: skipping scrape cache entry {:?}: {}", path, e),
+            }
+        }
+    }
+    println!("Loaded {} entries from scrape cache {:?}", loaded, dir);
+    Ok(cache)
+}
+
+/// Store entries of the scrape cache in the directory. Entries for releases which
+/// were scraped are rewritten, so that `prune` keeps them.
+/// Entries without a release have no manifest digest and are not stored
+pub async fn save(dir: &Path, cache: &Cache, scraped_sources: &HashSet<&str>) -> Fallible<()> {
+    create_dir_all(dir).context(format!("Creating {:?}", dir))?;
+    let entries = cache.read().await;
+    let mut added = 0;
+    for release in entries.values() {
+        let release = match release {
+            Some(r) => r,
+            None => continue,
+        };
+        let digest = match manifest_digest(release) {
+            Some(digest) => digest,
+            None => continue,
+        };
+        let path = entry_path(dir, digest);
+        let exists = path.exists();
+        if exists && !scraped_sources.contains(release.source.as_str()) {
+            continue;
+        }
+        write(&path, serde_json::to_string(release)?).context(format!("Writing {:?}", path))?;
+        if !exists {
+            added += 1;
+        }
+    }
+    println!("Saved {} new entries to scrape cache {:?}", added, dir);
+    Ok(())
+}
+
+/// Remove cache entries which were not used for the given number of days
+pub fn prune(dir: &Path, max_age_days: u64) -> Fallible<()> {
+    let max_age = Duration::from_secs(max_age_days * 24 * 60 * 60);
+    let now = SystemTime::now();
+    let paths = entry_paths(dir)?;
+    let mut removed = 0;
+    for path in paths.iter() {
+        let modified = path
+            .metadata()
+            .and_then(|m| m.modified())
+            .context(format!("Reading modification time of {:?}", path))?;
+        if now.duration_since(modified).unwrap_or_default() > max_age {
+            remove_file(path).context(format!("Removing {:?}", path))?;
+            removed += 1;
+        }
+    }
+    println!(
+        "Removed {} of {} entries from scrape cache {:?}",
+        removed,
+        paths.len(),
+        dir
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RELEASE: &str = include_str!("../tests/fixtures/releases/4.3.1+amd64.json");
+
+    #[test]
+    fn saved_entries_are_hit_after_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let release: registry::Release = serde_json::from_str(RELEASE).unwrap();
+        let key = cache_key(&release);
+        let source = release.source.clone();
+
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let cache = registry::cache::new();
+        runtime.block_on(async {
+            cache.write().await.insert(key, Some(release));
+            save(dir.path(), &cache, &HashSet::new()).await.unwrap();
+        });
+        assert!(dir
+            .path()
+            .join("sha256-c3cabb489081cf4b04138da9e919bd8ff61148aeea59c7a8564ed9abd5771ffb.json")
+            .exists());
+
+        let loaded = runtime.block_on(load(dir.path())).unwrap();
+        let entries = runtime.block_on(loaded.read());
+        match entries.get(&key) {
+            Some(Some(r)) => assert_eq!(r.source, source),
+            _ => panic!("release was not loaded under its scrape cache key"),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::graph::{Graph, GraphData};
+
+use anyhow::Result as Fallible;
+use std::collections::BTreeSet;
+
+/// Verify that every release but the latest one has an update path out of it
+/// in each channel and architecture
+pub fn run(graph: &Graph, data: &GraphData) -> Fallible<()> {
+    println!("Checking for stranded releases");
+    let mut errors: Vec<String> = vec![];
+    for ((channel, arch), filtered) in graph.channel_graphs(&data.channels) {
+        let latest = match filtered.nodes.iter().map(|n| &n.version).max() {
+            Some(v) => v,
+            None => continue,
+        };
+        for (i, node) in filtered.nodes.iter().enumerate() {
+            if node.version == *latest || filtered.served_edges().any(|e| e.from == i) {
+                continue;
+            }
+            let files: BTreeSet<String> = filtered
+                .edges
+                .iter()
+                .filter(|e| e.from == i)
+                .flat_map(|e| e.blocked_by.iter().map(|p| p.display().to_string()))
+                .collect();
+            let reason = if files.is_empty() {
+                "release metadata has no edges to other releases in the channel".to_string()
+            } else {
+                format!(
+                    "all outgoing edges are blocked by {}",
+                    files.into_iter().collect::<Vec<_>>().join(", ")
+                )
+            };
+            errors.push(format!(
+                "{} ({}): {} is stranded, {}",
+                channel,
+                arch,
+                node.release.version(),
+                reason
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Stranded releases: {:#?}", errors))
+    }
+}
@@ -0,0 +1,263 @@
+use crate::arch::Arch;
+use crate::graph::{Graph, GraphData};
+
+use anyhow::Result as Fallible;
+use semver::Version;
+use std::collections::{BTreeSet, VecDeque};
+
+// Maximum number of alternative paths to print
+static MAX_ALTERNATIVES: usize = 20;
+
+/// Hop count from `start` to every node, following edges forward or backward
+fn distances(
+    graph: &Graph,
+    start: usize,
+    include_blocked: bool,
+    forward: bool,
+) -> Vec<Option<usize>> {
+    let mut result: Vec<Option<usize>> = vec![None; graph.nodes.len()];
+    result[start] = Some(0);
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    queue.push_back(start);
+    while let Some(current) = queue.pop_front() {
+        let distance = result[current].unwrap_or_default();
+        for edge in graph.edges.iter() {
+            if edge.is_blocked() && !include_blocked {
+                continue;
+            }
+            let (source, target) = if forward {
+                (edge.from, edge.to)
+            } else {
+                (edge.to, edge.from)
+            };
+            if source == current && result[target].is_none() {
+                result[target] = Some(distance + 1);
+                queue.push_back(target);
+            }
+        }
+    }
+    result
+}
+
+/// Collect served paths from `current` to `to` no longer than `limit` hops.
+/// `to_target` holds served hop count of each node to the target and is used for pruning.
+/// Nodes closer to the target are visited first, so the first path found is a shortest one
+fn collect_paths(
+    graph: &Graph,
+    to_target: &[Option<usize>],
+    to: usize,
+    limit: usize,
+    path: &mut Vec<usize>,
+    result: &mut Vec<Vec<usize>>,
+) {
+    let current = *path.last().expect("path is not empty");
+    if current == to {
+        result.push(path.clone());
+        return;
+    }
+    if result.len() > MAX_ALTERNATIVES {
+        return;
+    }
+    let mut next: Vec<(usize, usize)> = graph
+        .served_edges()
+        .filter(|e| e.from == current && !path.contains(&e.to))
+        .filter_map(|e| match to_target[e.to] {
+            Some(d) if path.len() + d <= limit => Some((d, e.to)),
+            _ => None,
+        })
+        .collect();
+    next.sort();
+    for (_, node) in next {
+        path.push(node);
+        collect_paths(graph, to_target, to, limit, path, result);
+        path.pop();
+    }
+}
+
+fn format_path(graph: &Graph, path: &[usize]) -> String {
+    path.iter()
+        .map(|&i| graph.nodes[i].release.version())
+        .collect::<Vec<&str>>()
+        .join(" -> ")
+}
+
+/// Explain why there is no served path from `from` to `to` in the channel
+fn explain(channel_graph: &Graph, arch_graph: &Graph, from: usize, to: usize) -> Vec<String> {
+    let mut reasons: Vec<String> = vec![];
+
+    // Blocked edges which leave the served part of the graph towards the target
+    let reachable = distances(channel_graph, from, false, true);
+    let reaches_target = distances(channel_graph, to, true, false);
+    let files: BTreeSet<String> = channel_graph
+        .edges
+        .iter()
+        .filter(|e| e.is_blocked() && reachable[e.from].is_some() && reaches_target[e.to].is_some())
+        .flat_map(|e| e.blocked_by.iter().map(|p| p.display().to_string()))
+        .collect();
+    if !files.is_empty() {
+        reasons.push(format!(
+            "all paths are cut by blocked edges in: {}",
+            files.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+        return reasons;
+    }
+
+    // Path through releases which are not listed in the channel
+    let from_node = &channel_graph.nodes[from];
+    let to_node = &channel_graph.nodes[to];
+    let (arch_from, arch_to) = match (
+        arch_graph.find(&from_node.version),
+        arch_graph.find(&to_node.version),
+    ) {
+        (Some(f), Some(t)) => (f, t),
+        _ => return reasons,
+    };
+    let to_target = distances(arch_graph, arch_to, false, false);
+    if let Some(limit) = to_target[arch_from] {
+        let mut paths: Vec<Vec<usize>> = vec![];
+        collect_paths(
+            arch_graph,
+            &to_target,
+            arch_to,
+            limit + 1,
+            &mut vec![arch_from],
+            &mut paths,
+        );
+        if let Some(path) = paths.iter().min_by_key(|p| p.len()) {
+            let missing: Vec<&str> = path
+                .iter()
+                .filter(|&&i| channel_graph.find(&arch_graph.nodes[i].version).is_none())
+                .map(|&i| arch_graph.nodes[i].release.version())
+                .collect();
+            reasons.push(format!(
+                "path {} needs channel entries for: {}",
+                format_path(arch_graph, path),
+                missing.join(", ")
+            ));
+        }
+    } else {
+        reasons.push("release metadata doesn't connect these releases".to_string());
+    }
+    reasons
+}
+
+/// Print the shortest served path between two releases in a channel,
+/// along with alternatives which are at most one hop longer
+pub fn run(
+    graph: &Graph,
+    data: &GraphData,
+    channel: &str,
+    arch: Arch,
+    from: &Version,
+    to: &Version,
+) -> Fallible<()> {
+    let channel_graph = graph.filter(data.channel(channel)?, arch);
+    let find = |version: &Version| {
+        channel_graph
+            .find(version)
+            .ok_or_else(|| anyhow::anyhow!("{} ({}) is not listed in {}", version, arch, channel))
+    };
+    let (from_index, to_index) = (find(from)?, find(to)?);
+
+    let to_target = distances(&channel_graph, to_index, false, false);
+    let shortest = match to_target[from_index] {
+        Some(d) => d,
+        None => {
+            let reasons = explain(
+                &channel_graph,
+                &graph.filter_arch(arch),
+                from_index,
+                to_index,
+            );
+            return Err(anyhow::anyhow!(
+                "No update path from {} to {} in {} ({}): {:#?}",
+                from,
+                to,
+                channel,
+                arch,
+                reasons
+            ));
+        }
+    };
+
+    let mut paths: Vec<Vec<usize>> = vec![];
+    collect_paths(
+        &channel_graph,
+        &to_target,
+        to_index,
+        shortest + 1,
+        &mut vec![from_index],
+        &mut paths,
+    );
+    paths.sort_by_key(|p| p.len());
+    debug_assert_eq!(paths[0].len() - 1, shortest);
+
+    println!(
+        "Shortest path ({} hops): {}",
+        shortest,
+        format_path(&channel_graph, &paths[0])
+    );
+    if paths.len() > 1 {
+        println!("Alternatives:");
+        for path in paths.iter().skip(1).take(MAX_ALTERNATIVES) {
+            println!(
+                "  ({} hops) {}",
+                path.len() - 1,
+                format_path(&channel_graph, path)
+            );
+        }
+        if paths.len() > MAX_ALTERNATIVES + 1 {
+            println!("  ... more alternatives omitted");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Edge, Node};
+    use cincinnati::{ConcreteRelease, Release};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn node(patch: u64) -> Node {
+        let version = format!("4.4.{}", patch);
+        Node {
+            release: Release::Concrete(ConcreteRelease {
+                version: version.clone(),
+                payload: String::new(),
+                metadata: HashMap::new(),
+            }),
+            version: Version::from_str(&version).unwrap(),
+            arch: Arch::Amd64,
+        }
+    }
+
+    fn edge(from: usize, to: usize) -> Edge {
+        Edge {
+            from,
+            to,
+            blocked_by: vec![],
+        }
+    }
+
+    #[test]
+    fn shortest_path_is_found_before_alternatives_fill_up() {
+        // Direct edge to the target, but more two hop alternatives through
+        // lower numbered nodes than MAX_ALTERNATIVES
+        let target = MAX_ALTERNATIVES + 10;
+        let nodes: Vec<Node> = (0..=target as u64).map(node).collect();
+        let mut edges: Vec<Edge> = (1..target).map(|i| edge(0, i)).collect();
+        edges.push(edge(0, target));
+        edges.extend((1..target).map(|i| edge(i, target)));
+        let graph = Graph { nodes, edges };
+
+        let to_target = distances(&graph, target, false, false);
+        assert_eq!(to_target[0], Some(1));
+        let mut paths: Vec<Vec<usize>> = vec![];
+        collect_paths(&graph, &to_target, target, 2, &mut vec![0], &mut paths);
+        assert_eq!(paths[0], vec![0, target]);
+        assert!(paths.len() > MAX_ALTERNATIVES);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::check_signatures::SignatureStore;
+use crate::gpg::{self, Keyring};
+
+use anyhow::Context;
+use anyhow::Result as Fallible;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+// Data key prefixes of the release verification ConfigMap, see
+// https://github.com/openshift/cluster-update-keys/blob/master/manifests/0000_90_cluster-update-keys_configmap.yaml
+static STORE_PREFIX: &str = "store-";
+static PUBLIC_KEY_PREFIX: &str = "verifier-public-key-";
+
+#[derive(Deserialize)]
+struct ConfigMap {
+    kind: String,
+    #[serde(default)]
+    data: BTreeMap<String, String>,
+}
+
+/// Signature stores, tried in order, and public keys used to verify release signatures
+pub struct VerificationConfig {
+    pub stores: Vec<SignatureStore>,
+    pub keys: Keyring,
+}
+
+impl VerificationConfig {
+    /// Read the release verification ConfigMap manifest which the CVO consumes.
+    /// Like the CVO, stores are tried in order of their data keys
+    pub fn read(path: &Path) -> Fallible<Self> {
+        let contents = read_to_string(path).context(format!("Reading {:?}", path))?;
+        let manifest: ConfigMap =
+            serde_yaml::from_str(&contents).context(format!("Parsing {:?}", path))?;
+        if manifest.kind != "ConfigMap" {
+            return Err(anyhow::anyhow!(
+                "{:?} is a {}, expected a ConfigMap",
+                path,
+                manifest.kind
+            ));
+        }
+
+        let mut stores: Vec<SignatureStore> = vec![];
+        let mut keys: Keyring = vec![];
+        for (name, value) in manifest.data.iter() {
+            if name.starts_with(STORE_PREFIX) {
+                stores.push(
+                    SignatureStore::from_str(value.trim())
+                        .context(format!("{:?}: {}", path, name))?,
+                );
+            } else if name.starts_with(PUBLIC_KEY_PREFIX) {
+                keys.extend(
+                    gpg::read_public_keys(value.as_bytes(), name)
+                        .context(format!("{:?}: {}", path, name))?,
+                );
+            }
+        }
+        if stores.is_empty() || keys.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{:?} must have {}* and {}* data keys",
+                path,
+                STORE_PREFIX,
+                PUBLIC_KEY_PREFIX
+            ));
+        }
+        println!(
+            "Loaded {} signature stores and {} public keys from {:?}",
+            stores.len(),
+            keys.len(),
+            path
+        );
+        Ok(VerificationConfig { stores, keys })
+    }
+}
@@ -1,14 +1,31 @@
+use crate::arch::ArchVersion;
+use crate::blocked_edges;
+use crate::build_suggestions;
+use crate::channels;
+use crate::raw_metadata;
+use crate::schema_version;
+
 use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin;
 use cincinnati::plugins::internal::openshift_secondary_metadata_parser::plugin::graph_data_model::{BlockedEdge, Channel};
 use regex::Regex;
 use semver::Version;
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use anyhow::Context;
 use anyhow::Result as Fallible;
 
-pub async fn run() -> Fallible<HashSet<Version>> {
-    let data_dir = PathBuf::from(".");
-    println!("Looking for metadata in {:?}", data_dir.canonicalize()?);
+/// Resolve a path relative to the data directory, reporting the attempted path if it is missing
+pub fn resolve_path(data_dir: &Path, name: &str) -> Fallible<PathBuf> {
+    let path = data_dir.join(name);
+    path.canonicalize().context(format!("Resolving {:?} in data directory", path))
+}
+
+pub async fn run(data_dir: &Path) -> Fallible<HashSet<ArchVersion>> {
+    let data_dir = data_dir
+        .canonicalize()
+        .context(format!("Resolving data directory {:?}", data_dir))?;
+    println!("Looking for metadata in {:?}", data_dir);
+    schema_version::run(&data_dir)?;
     let all_files_regex = Regex::new(".*")?;
     let disallowed_errors: HashSet<plugin::DeserializeDirectoryFilesErrorDiscriminants> = [
         plugin::DeserializeDirectoryFilesErrorDiscriminants::File,
@@ -20,10 +37,10 @@ pub async fn run() -> Fallible<HashSet<Version>> {
     .cloned()
     .collect();
     // Collect a list of mentioned versions
-    let mut found_versions: HashSet<Version> = HashSet::new();
+    let mut found_versions: HashSet<ArchVersion> = HashSet::new();
 
     println!("Verifying blocked edge files are valid");
-    let blocked_edge_path = data_dir.join(plugin::BLOCKED_EDGES_DIR).canonicalize()?;
+    let blocked_edge_path = resolve_path(&data_dir, plugin::BLOCKED_EDGES_DIR)?;
     let blocked_edge_vec = plugin::deserialize_directory_files::<BlockedEdge>(
         &blocked_edge_path,
         all_files_regex.clone(),
@@ -31,22 +48,35 @@ pub async fn run() -> Fallible<HashSet<Version>> {
     )
     .await?;
     for v in blocked_edge_vec.iter() {
-        found_versions.insert(v.to.clone());
+        found_versions.insert(
+            ArchVersion::parse(&v.to).context(format!("Blocked edge to {}", v.to))?,
+        );
     }
 
     println!("Verifying channel files are valid");
-    let channel_path = data_dir.join(plugin::CHANNELS_DIR).canonicalize()?;
+    let channel_path = resolve_path(&data_dir, plugin::CHANNELS_DIR)?;
     let channels_vec = plugin::deserialize_directory_files::<Channel>(
         &channel_path,
         all_files_regex.clone(),
         &disallowed_errors,
     )
     .await?;
+    let mut channel_versions: HashSet<Version> = HashSet::new();
     for c in channels_vec.iter() {
         for v in c.versions.iter() {
-            found_versions.insert(v.clone());
+            channel_versions.insert(v.clone());
+            found_versions
+                .insert(ArchVersion::parse(v).context(format!("Channel {}", c.name))?);
         }
     }
+    channels::verify_promotion(&channels_vec)?;
+    channels::verify_order(&channels_vec)?;
+
+    let blocked_edge_files = blocked_edges::read(&blocked_edge_path)?;
+    blocked_edges::verify(&blocked_edge_files, &channel_versions)?;
+
+    build_suggestions::run(&data_dir)?;
+    raw_metadata::run(&data_dir)?;
 
     Ok(found_versions)
 }
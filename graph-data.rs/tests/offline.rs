@@ -0,0 +1,107 @@
+//! Run the checks offline against the fixture tree in `tests/fixtures`:
+//! graph data in `data`, recorded releases in `releases` and their signatures,
+//! made with the key in `public-keys`, in `signatures`
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+}
+
+fn run(store: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cincinnati-graph-data"))
+        .current_dir(fixtures_dir())
+        .arg("--data-dir=data")
+        .arg("--offline")
+        .arg("--registry-fixtures=releases")
+        .arg(format!("--signature-store={}", store))
+        .arg("--keyring=public-keys")
+        .args(args)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .expect("running cincinnati-graph-data")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "stdout:\n{}\nstderr:\n{}",
+        stdout(output),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn all_checks_pass_with_fixtures_and_local_signatures() {
+    let output = run("signatures", &["all"]);
+    assert_success(&output);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("Checking release signatures in signatures"));
+    assert!(!stdout.contains("SKIPPED"));
+}
+
+#[test]
+fn releases_with_unknown_architectures_are_skipped() {
+    let output = run("signatures", &["check-releases"]);
+    assert_success(&output);
+    assert!(stdout(&output).contains("Warning: skipping release 4.3.2+arm64"));
+}
+
+#[test]
+fn graph_is_built_from_fixtures() {
+    let output = run(
+        "signatures",
+        &["graph", "show", "--channel=candidate-4.3", "--arch=amd64"],
+    );
+    assert_success(&output);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("3 nodes, 2 edges, 1 blocked"));
+    assert!(stdout.contains("4.3.0+amd64 -> 4.3.1+amd64\n"));
+    assert!(stdout.contains("4.3.1+amd64 -> 4.3.2+amd64\n"));
+    assert!(stdout.contains("4.3.0+amd64 -> 4.3.2+amd64 blocked by"));
+}
+
+#[test]
+fn stranded_releases_are_checked_offline() {
+    assert_success(&run("signatures", &["check-stranded"]));
+}
+
+#[test]
+fn remote_signature_store_is_skipped_offline() {
+    let store = "https://example.com/signatures/";
+    let output = run(store, &["all"]);
+    assert_success(&output);
+    assert!(stdout(&output).contains("check-signatures was SKIPPED"));
+
+    let output = run(store, &["check-signatures"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn graph_is_rendered_to_stdout_without_output() {
+    let output = run(
+        "signatures",
+        &["graph", "render", "--channel=candidate-4.3", "--format=dot"],
+    );
+    assert_success(&output);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("digraph"));
+    assert!(!stdout.contains("Rendered"));
+}
+
+#[test]
+fn verification_config_conflicts_with_stores_and_keyrings() {
+    let output = run(
+        "signatures",
+        &["--verification-config=verification.yaml", "all"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}